@@ -0,0 +1,137 @@
+use super::prelude::*;
+use super::enemy::Enemy;
+use super::route::Route;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum State {
+    // coming onto the screen
+    Enter,
+    // hanging around, not shooting yet
+    Patrol,
+    // the only state emitters fire in
+    Attack,
+    // backing off to somewhere safer
+    Retreat,
+    // running off the screen for good
+    Flee,
+}
+
+#[derive(Clone, Copy)]
+pub enum Trigger {
+    // after this many ticks in the state
+    After(usize),
+    // once health drops to this or lower
+    Health(usize),
+    // once the player comes closer than this
+    Near(f32),
+    // once the player gets further away than this
+    Far(f32),
+    // once the state's route has been finished
+    Arrived,
+}
+
+#[derive(Clone)]
+struct Transition {
+    from: State,
+    trigger: Trigger,
+    to: State,
+}
+
+// decides what an enemy is doing based on what's going on around it,
+// giving it a different route for each state.
+#[derive(Clone)]
+pub struct Behaviour {
+    pub state: State,
+    // ticks spent in the current state
+    pub ticks: usize,
+    routes: HashMap<State, Route>,
+    transitions: Vec<Transition>,
+    // how fast the enemy runs once it's fleeing, if there's no route for that
+    flee_speed: f32,
+}
+
+impl Behaviour {
+    pub fn new() -> Self {
+        Behaviour {
+            state: State::Enter,
+            ticks: 0,
+            routes: HashMap::new(),
+            transitions: Vec::new(),
+            flee_speed: 0.5,
+        }
+    }
+
+    // the route to follow while in `state`.
+    // without one, enemies stop where they are, or run from the player when fleeing.
+    pub fn route(mut self, state: State, route: Route) -> Self {
+        self.routes.insert(state, route);
+        self
+    }
+
+    // go from one state to another as soon as `trigger` happens.
+    // transitions are checked in the order they were added.
+    pub fn on(mut self, from: State, trigger: Trigger, to: State) -> Self {
+        self.transitions.push(Transition { from, trigger, to });
+        self
+    }
+
+    pub fn flee_speed(mut self, speed: f32) -> Self {
+        self.flee_speed = speed;
+        self
+    }
+
+    pub fn shooting(&self) -> bool {
+        self.state == State::Attack
+    }
+
+    // puts the enemy on the route for the state it starts in
+    pub fn start(&self, enemy: &mut Enemy) {
+        if let Some(route) = self.routes.get(&self.state) {
+            enemy.follow(route.clone());
+        }
+    }
+
+    pub fn update(&mut self, enemy: &mut Enemy, pos: &Vector2<f32>, player: &Vector2<f32>) {
+        self.ticks += 1;
+
+        let distance = (player - pos).magnitude();
+        let next = self
+            .transitions
+            .iter()
+            .filter(|transition| transition.from == self.state)
+            .find(|transition| match transition.trigger {
+                Trigger::After(ticks) => self.ticks >= ticks,
+                Trigger::Health(health) => enemy.health <= health,
+                Trigger::Near(radius) => distance < radius,
+                Trigger::Far(radius) => distance > radius,
+                Trigger::Arrived => enemy.finished,
+            })
+            .map(|transition| transition.to);
+
+        if let Some(state) = next {
+            self.enter(state, enemy, pos, player);
+        }
+    }
+
+    fn enter(&mut self, state: State, enemy: &mut Enemy, pos: &Vector2<f32>, player: &Vector2<f32>) {
+        self.state = state;
+        self.ticks = 0;
+
+        match self.routes.get(&state) {
+            Some(route) => enemy.follow(route.clone()),
+            None => {
+                enemy.follow(Route::new(0.0));
+                if state == State::Flee {
+                    let away = pos - player;
+                    let away = if away.magnitude() > 0.0 {
+                        away.normalize()
+                    } else {
+                        -Vector2::y()
+                    };
+                    enemy.exit = Some(away * self.flee_speed);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use super::*;
+
+#[derive(Clone)]
+pub struct Phase {
+    pub health: usize,
+    pub route: Route,
+    pub emitters: Vec<Emitter>,
+    // ticks the player has to get through the phase before it ends by itself
+    pub time_limit: usize,
+    // spell cards have a name, shown when they start,
+    // and a bonus for getting through them without dying or bombing
+    pub name: Option<String>,
+    pub bonus: usize,
+}
+
+impl Phase {
+    pub fn new(health: usize, route: Route, emitters: Vec<Emitter>, time_limit: usize) -> Self {
+        Phase {
+            health,
+            route,
+            emitters,
+            time_limit,
+            name: None,
+            bonus: 0,
+        }
+    }
+
+    pub fn spell_card<S: Into<String>>(mut self, name: S, bonus: usize) -> Self {
+        self.name = Some(name.into());
+        self.bonus = bonus;
+        self
+    }
+}
+
+// an enemy that goes through several phases before it's beaten
+#[derive(Clone)]
+pub struct Boss {
+    pub phases: Vec<Phase>,
+    pub phase: usize,
+    // ticks left before the current phase runs out of time
+    pub timer: usize,
+    // cleared when the player dies or bombs during a spell card
+    pub capturing: bool,
+}
+
+impl Boss {
+    pub fn new(phases: Vec<Phase>) -> Self {
+        let timer = phases.first().expect("boss with no phases").time_limit;
+
+        Boss {
+            phases,
+            phase: 0,
+            timer,
+            capturing: true,
+        }
+    }
+
+    pub fn current(&self) -> &Phase {
+        &self.phases[self.phase]
+    }
+
+    pub fn phases_left(&self) -> usize {
+        self.phases.len() - self.phase - 1
+    }
+
+    // counts down the phase's time limit,
+    // returns a boolean indicating whether or not it just ran out.
+    pub fn update(&mut self) -> bool {
+        self.timer = self.timer.saturating_sub(1);
+        self.timer == 0
+    }
+
+    // jumps straight to `phase`, or the last one if there aren't that many
+    pub fn skip_to(&mut self, phase: usize) {
+        self.phase = phase.min(self.phases.len() - 1);
+        self.timer = self.current().time_limit;
+        self.capturing = true;
+    }
+
+    // moves on to the next phase, if there are any left
+    pub fn next_phase(&mut self) -> Option<&Phase> {
+        if self.phases_left() == 0 {
+            return None;
+        }
+
+        self.phase += 1;
+        self.timer = self.current().time_limit;
+        self.capturing = true;
+        Some(self.current())
+    }
+}
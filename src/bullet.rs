@@ -1,10 +1,36 @@
 use super::prelude::*;
 
+// how many ticks a bullet takes to fade away once its lifetime is up
+const FADE_TICKS: usize = 10;
+
+#[derive(Clone)]
 pub struct Bullet {
     pub kind: BulletKind,
     pub kills: BulletKills,
+    // how many more times this bullet can reflect off
+    // the edges of the screen before it is despawned
+    pub bounces: usize,
+    // set for bullets that bounce, which are kept to the play field
+    // and go as soon as they leave it once they're out of bounces
+    pub confined: bool,
+    // whether the bullet turns its local y axis to face where it's going,
+    // for long bullets with hitboxes that aren't the same all the way around
+    pub oriented: bool,
+    // ticks left before the bullet starts fading out, or None to last until it leaves the screen
+    pub lifetime: Option<usize>,
+    // ticks left where the bullet sits still and can be seen, but can't hurt anything yet
+    pub delay: usize,
+    // ticks left in the fade out, once the bullet has started despawning
+    pub fading: Option<usize>,
+    // whether or not the bullet has already counted as a graze
+    pub grazed: bool,
+    // how much health it takes off whatever it hits, before armour
+    pub damage: usize,
+    // radians per tick the bullet can turn toward its target, if it has one
+    pub homing: Option<f32>,
 }
 
+#[derive(Clone)]
 pub enum BulletKind {
     Straight(Vector2<f32>),
     /*
@@ -15,6 +41,7 @@ pub enum BulletKind {
     },*/
 }
 
+#[derive(Clone)]
 pub enum BulletKills {
     Good,
     Bad,
@@ -25,18 +52,157 @@ impl Bullet {
     pub fn update(&mut self, pos: &mut Isometry2<f32>) {
         use BulletKind::*;
 
+        if self.delay > 0 {
+            self.delay -= 1;
+            return;
+        }
+
+        if let Some(fading) = self.fading.as_mut() {
+            *fading = fading.saturating_sub(1);
+        } else if let Some(lifetime) = self.lifetime.as_mut() {
+            if *lifetime == 0 {
+                self.despawn();
+            } else {
+                *lifetime -= 1;
+            }
+        }
+
         match self.kind {
             Straight(trajectory) => {
                 pos.translation.vector += trajectory;
+
+                if self.oriented {
+                    pos.rotation = na::UnitComplex::rotation_between(&Vector2::y(), &trajectory);
+                }
             }
         }
     }
 
-    /*
+    // starts fading the bullet out, it can't hurt anything while it does
+    pub fn despawn(&mut self) {
+        if self.fading.is_none() {
+            self.fading = Some(FADE_TICKS);
+        }
+    }
+
+    // whether or not the bullet can hit anything right now
+    pub fn harmful(&self) -> bool {
+        self.delay == 0 && self.fading.is_none()
+    }
+
+    // whether or not the bullet has finished fading out and should be removed
+    pub fn expired(&self) -> bool {
+        self.fading == Some(0)
+    }
+
+    // how see-through the renderer should draw the bullet
+    pub fn alpha(&self) -> f32 {
+        match self.fading {
+            _ if self.delay > 0 => 0.4,
+            Some(fading) => fading as f32 / FADE_TICKS as f32,
+            None => 1.0,
+        }
+    }
+
+    // reflects a bullet that has left the play field back onto it, where the field
+    // runs from 0 to `field` on each axis. returns whether or not it bounced.
+    pub fn bounce(&mut self, pos: &mut Isometry2<f32>, field: &Vector2<f32>) -> bool {
+        use BulletKind::*;
+
+        if self.bounces == 0 {
+            return false;
+        }
+
+        let vec = &mut pos.translation.vector;
+        let mut bounced = false;
+        match &mut self.kind {
+            Straight(trajectory) => {
+                for axis in 0..2 {
+                    // mirror the position across the edge it went past,
+                    // unless it's already on its way back
+                    if vec[axis] < 0.0 && trajectory[axis] < 0.0 {
+                        vec[axis] = -vec[axis];
+                    } else if vec[axis] > field[axis] && trajectory[axis] > 0.0 {
+                        vec[axis] = field[axis] * 2.0 - vec[axis];
+                    } else {
+                        continue;
+                    }
+                    trajectory[axis] = -trajectory[axis];
+                    bounced = true;
+                }
+            }
+        }
+        if bounced {
+            self.bounces -= 1;
+        }
+
+        bounced
+    }
+
     pub fn straight(trajectory: Vector2<f32>, kills: BulletKills) -> Self {
         Bullet {
             kind: BulletKind::Straight(trajectory),
             kills,
+            bounces: 0,
+            confined: false,
+            oriented: false,
+            lifetime: None,
+            delay: 0,
+            fading: None,
+            grazed: false,
+            damage: 1,
+            homing: None,
+        }
+    }
+
+    pub fn bouncing(mut self, bounces: usize) -> Self {
+        self.bounces = bounces;
+        self.confined = true;
+        self
+    }
+
+    pub fn oriented(mut self) -> Self {
+        self.oriented = true;
+        self
+    }
+
+    pub fn lifetime(mut self, ticks: usize) -> Self {
+        self.lifetime = Some(ticks);
+        self
+    }
+
+    pub fn delayed(mut self, ticks: usize) -> Self {
+        self.delay = ticks;
+        self
+    }
+
+    pub fn damage(mut self, damage: usize) -> Self {
+        self.damage = damage;
+        self
+    }
+
+    pub fn homing(mut self, turn: f32) -> Self {
+        self.homing = Some(turn);
+        self
+    }
+
+    // turns a homing bullet a little toward `target`, keeping its speed
+    pub fn steer(&mut self, pos: &Vector2<f32>, target: &Vector2<f32>) {
+        use BulletKind::*;
+
+        let turn = match self.homing {
+            Some(turn) => turn,
+            None => return,
+        };
+        match &mut self.kind {
+            Straight(trajectory) => {
+                let to_target = target - pos;
+                if to_target.magnitude() == 0.0 || trajectory.magnitude() == 0.0 {
+                    return;
+                }
+                let angle = na::UnitComplex::rotation_between(trajectory, &to_target).angle();
+                *trajectory = na::UnitComplex::new(angle.max(-turn).min(turn)) * *trajectory;
+            }
         }
-    }*/
+    }
 }
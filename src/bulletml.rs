@@ -0,0 +1,1034 @@
+// compiles BulletML (http://www.asahi-net.or.jp/~cs8k-cyu/bulletml/index_e.html)
+// patterns into scripts that are run once a tick for whatever they're attached to.
+//
+// bulletml measures directions in degrees clockwise from straight up,
+// and speeds in pixels per frame, so those are converted as bullets are fired.
+use super::*;
+use std::rc::Rc;
+
+// one bulletml pixel is a tenth of one of our units
+const SPEED_SCALE: f32 = 0.1;
+
+// keeps a script with a repeat that never waits from locking up the game
+const MAX_STEPS: usize = 10_000;
+
+pub struct Document {
+    // the actions labeled "top", "top1", "top2" and so on, which all start at once
+    tops: Vec<Rc<Vec<Command>>>,
+    actions: HashMap<String, Rc<Vec<Command>>>,
+    bullets: HashMap<String, Rc<BulletDef>>,
+    fires: HashMap<String, Rc<FireDef>>,
+}
+
+#[derive(Clone)]
+enum Expr {
+    Num(f32),
+    Rand,
+    Rank,
+    // $1 is the first parameter
+    Param(usize),
+    Neg(Box<Expr>),
+    Op(Box<Expr>, char, Box<Expr>),
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Aim,
+    Absolute,
+    Relative,
+    Sequence,
+}
+
+#[derive(Clone)]
+struct Amount {
+    kind: Kind,
+    value: Expr,
+}
+
+#[derive(Clone)]
+enum Command {
+    Fire(Rc<FireDef>),
+    FireRef(String, Vec<Expr>),
+    ChangeDirection { direction: Amount, term: Expr },
+    ChangeSpeed { speed: Amount, term: Expr },
+    // the body is a single action or actionRef
+    Repeat { times: Expr, body: Rc<Vec<Command>> },
+    Wait(Expr),
+    Vanish,
+    Action(Rc<Vec<Command>>),
+    ActionRef(String, Vec<Expr>),
+}
+
+struct BulletDef {
+    direction: Option<Amount>,
+    speed: Option<Amount>,
+    actions: Vec<Rc<Vec<Command>>>,
+}
+
+enum BulletSource {
+    Inline(Rc<BulletDef>),
+    Ref(String, Vec<Expr>),
+}
+
+struct FireDef {
+    direction: Option<Amount>,
+    speed: Option<Amount>,
+    bullet: BulletSource,
+}
+
+impl Document {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let root = Reader { src, at: 0 }.document()?;
+        if root.name != "bulletml" {
+            return Err(format!("expected <bulletml>, found <{}>", root.name));
+        }
+
+        let mut doc = Document {
+            tops: Vec::new(),
+            actions: HashMap::new(),
+            bullets: HashMap::new(),
+            fires: HashMap::new(),
+        };
+        for el in root.children.iter() {
+            let label = match el.attrs.get("label") {
+                Some(label) => label.clone(),
+                None => continue,
+            };
+
+            match el.name.as_str() {
+                "action" => {
+                    let action = Rc::new(action(el)?);
+                    if label.starts_with("top") {
+                        doc.tops.push(action.clone());
+                    }
+                    doc.actions.insert(label, action);
+                }
+                "bullet" => {
+                    doc.bullets.insert(label, Rc::new(bullet(el)?));
+                }
+                "fire" => {
+                    doc.fires.insert(label, Rc::new(fire(el)?));
+                }
+                other => return Err(format!("unexpected <{}> in <bulletml>", other)),
+            }
+        }
+
+        if doc.tops.is_empty() {
+            return Err("no action labeled \"top\"".to_string());
+        }
+        Ok(doc)
+    }
+}
+
+// what a script fired this tick
+pub struct Shot {
+    pub appearance: String,
+    pub hitbox: ShapeHandle<f32>,
+    pub velocity: Vector2<f32>,
+    // bullets with actions of their own keep running a script after they're fired
+    pub script: Option<Runner>,
+}
+
+// tracks a change of direction or speed that's spread over several ticks
+struct Change {
+    per_tick: f32,
+    ticks: usize,
+}
+
+struct Frame {
+    commands: Rc<Vec<Command>>,
+    next: usize,
+    // times left to run through the commands, including this one
+    repeat: usize,
+    params: Rc<Vec<f32>>,
+}
+
+struct Thread {
+    stack: Vec<Frame>,
+    wait: usize,
+}
+
+pub struct Runner {
+    doc: Rc<Document>,
+    threads: Vec<Thread>,
+    // degrees clockwise from straight up
+    direction: f32,
+    speed: f32,
+    turn: Option<Change>,
+    accel: Option<Change>,
+    // what the last bullet was fired with, for sequence directions and speeds
+    last_direction: f32,
+    last_speed: f32,
+    vanished: bool,
+    // the 0 to 1 difficulty bulletml patterns read through $rank
+    pub rank: f32,
+    // where $rand comes from
+    random: fn() -> f32,
+    appearance: String,
+    hitbox: ShapeHandle<f32>,
+}
+
+impl Runner {
+    // runs the top actions of a document from whatever it's attached to,
+    // firing bullets that look like `appearance` and collide like `hitbox`.
+    pub fn new<S: Into<String>, H: Shape<f32>>(doc: Rc<Document>, appearance: S, hitbox: H) -> Self {
+        let threads = doc
+            .tops
+            .iter()
+            .map(|top| Thread::new(top.clone(), Rc::new(Vec::new())))
+            .collect();
+
+        Runner {
+            doc,
+            threads,
+            direction: 180.0,
+            speed: 0.0,
+            turn: None,
+            accel: None,
+            last_direction: 180.0,
+            last_speed: 1.0,
+            vanished: false,
+            rank: 0.5,
+            random,
+            appearance: appearance.into(),
+            hitbox: ShapeHandle::new(hitbox),
+        }
+    }
+
+    // the direction and speed the script wants whatever it's attached to moving in
+    pub fn velocity(&self) -> Vector2<f32> {
+        let rad = self.direction.to_radians();
+        Vector2::new(rad.sin(), -rad.cos()) * self.speed * SPEED_SCALE
+    }
+
+    // whether or not the script has asked for its bullet to be removed
+    pub fn vanished(&self) -> bool {
+        self.vanished
+    }
+
+    // advances every thread of the script by a tick,
+    // returns the bullets fired from `pos` along the way.
+    pub fn update(&mut self, pos: &Vector2<f32>, player: &Vector2<f32>) -> Vec<Shot> {
+        if let Some(turn) = self.turn.as_mut() {
+            self.direction += turn.per_tick;
+            turn.ticks -= 1;
+            if turn.ticks == 0 {
+                self.turn = None;
+            }
+        }
+        if let Some(accel) = self.accel.as_mut() {
+            self.speed += accel.per_tick;
+            accel.ticks -= 1;
+            if accel.ticks == 0 {
+                self.accel = None;
+            }
+        }
+
+        let mut shots = Vec::new();
+        let mut threads = std::mem::replace(&mut self.threads, Vec::new());
+        for thread in threads.iter_mut() {
+            self.run(thread, pos, player, &mut shots);
+        }
+        threads.retain(|thread| !thread.stack.is_empty());
+        self.threads = threads;
+
+        shots
+    }
+
+    fn run(&mut self, thread: &mut Thread, pos: &Vector2<f32>, player: &Vector2<f32>, shots: &mut Vec<Shot>) {
+        if thread.wait > 0 {
+            thread.wait -= 1;
+            return;
+        }
+
+        for _ in 0..MAX_STEPS {
+            let (commands, next, params) = match thread.stack.last_mut() {
+                Some(frame) if frame.next < frame.commands.len() => {
+                    frame.next += 1;
+                    (frame.commands.clone(), frame.next - 1, frame.params.clone())
+                }
+                Some(frame) if frame.repeat > 1 => {
+                    frame.repeat -= 1;
+                    frame.next = 0;
+                    continue;
+                }
+                Some(_) => {
+                    thread.stack.pop();
+                    continue;
+                }
+                None => return,
+            };
+
+            match &commands[next] {
+                Command::Fire(fire) => shots.push(self.fire(fire, &params, pos, player)),
+                Command::FireRef(label, args) => {
+                    let fire = match self.doc.fires.get(label) {
+                        Some(fire) => fire.clone(),
+                        None => continue,
+                    };
+                    let args = Rc::new(self.eval_all(args, &params));
+                    shots.push(self.fire(&fire, &args, pos, player));
+                }
+                Command::ChangeDirection { direction, term } => {
+                    let ticks = self.eval(term, &params).max(1.0);
+                    let value = self.eval(&direction.value, &params);
+                    let per_tick = match direction.kind {
+                        Kind::Sequence => value,
+                        Kind::Aim => wrap(aim(pos, player) + value - self.direction) / ticks,
+                        Kind::Absolute => wrap(value - self.direction) / ticks,
+                        Kind::Relative => wrap(value) / ticks,
+                    };
+                    self.turn = Some(Change { per_tick, ticks: ticks as usize });
+                }
+                Command::ChangeSpeed { speed, term } => {
+                    let ticks = self.eval(term, &params).max(1.0);
+                    let value = self.eval(&speed.value, &params);
+                    let per_tick = match speed.kind {
+                        Kind::Sequence => value,
+                        Kind::Relative => value / ticks,
+                        _ => (value - self.speed) / ticks,
+                    };
+                    self.accel = Some(Change { per_tick, ticks: ticks as usize });
+                }
+                Command::Repeat { times, body } => {
+                    let times = self.eval(times, &params).max(0.0) as usize;
+                    if times > 0 {
+                        thread.stack.push(Frame {
+                            commands: body.clone(),
+                            next: 0,
+                            repeat: times,
+                            params,
+                        });
+                    }
+                }
+                Command::Wait(ticks) => {
+                    thread.wait = (self.eval(ticks, &params).max(1.0) as usize) - 1;
+                    return;
+                }
+                Command::Vanish => {
+                    self.vanished = true;
+                    thread.stack.clear();
+                    return;
+                }
+                Command::Action(action) => thread.stack.push(Frame::new(action.clone(), params)),
+                Command::ActionRef(label, args) => {
+                    if let Some(action) = self.doc.actions.get(label).cloned() {
+                        let args = Rc::new(self.eval_all(args, &params));
+                        thread.stack.push(Frame::new(action, args));
+                    }
+                }
+            }
+        }
+    }
+
+    fn fire(&mut self, fire: &FireDef, params: &Rc<Vec<f32>>, pos: &Vector2<f32>, player: &Vector2<f32>) -> Shot {
+        let (bullet, bullet_params) = match &fire.bullet {
+            BulletSource::Inline(bullet) => (Some(bullet.clone()), params.clone()),
+            BulletSource::Ref(label, args) => (
+                self.doc.bullets.get(label).cloned(),
+                Rc::new(self.eval_all(args, params)),
+            ),
+        };
+
+        // what the fire says wins out over what the bullet says
+        let direction = fire
+            .direction
+            .as_ref()
+            .map(|direction| (direction, params))
+            .or_else(|| {
+                let bullet = bullet.as_ref()?;
+                Some((bullet.direction.as_ref()?, &bullet_params))
+            });
+        let direction = match direction {
+            Some((direction, params)) => {
+                let value = self.eval(&direction.value, params);
+                match direction.kind {
+                    Kind::Aim => aim(pos, player) + value,
+                    Kind::Absolute => value,
+                    Kind::Relative => self.direction + value,
+                    Kind::Sequence => self.last_direction + value,
+                }
+            }
+            None => aim(pos, player),
+        };
+
+        let speed = fire
+            .speed
+            .as_ref()
+            .map(|speed| (speed, params))
+            .or_else(|| {
+                let bullet = bullet.as_ref()?;
+                Some((bullet.speed.as_ref()?, &bullet_params))
+            });
+        let speed = match speed {
+            Some((speed, params)) => {
+                let value = self.eval(&speed.value, params);
+                match speed.kind {
+                    Kind::Relative => self.speed + value,
+                    Kind::Sequence => self.last_speed + value,
+                    _ => value,
+                }
+            }
+            None => 1.0,
+        };
+
+        self.last_direction = direction;
+        self.last_speed = speed;
+
+        let script = bullet
+            .filter(|bullet| !bullet.actions.is_empty())
+            .map(|bullet| Runner {
+                doc: self.doc.clone(),
+                threads: bullet
+                    .actions
+                    .iter()
+                    .map(|action| Thread::new(action.clone(), bullet_params.clone()))
+                    .collect(),
+                direction,
+                speed,
+                turn: None,
+                accel: None,
+                last_direction: direction,
+                last_speed: speed,
+                vanished: false,
+                rank: self.rank,
+                random: self.random,
+                appearance: self.appearance.clone(),
+                hitbox: self.hitbox.clone(),
+            });
+
+        let rad = direction.to_radians();
+        Shot {
+            appearance: self.appearance.clone(),
+            hitbox: self.hitbox.clone(),
+            velocity: Vector2::new(rad.sin(), -rad.cos()) * speed * SPEED_SCALE,
+            script,
+        }
+    }
+
+    fn eval_all(&self, exprs: &[Expr], params: &[f32]) -> Vec<f32> {
+        exprs.iter().map(|expr| self.eval(expr, params)).collect()
+    }
+
+    fn eval(&self, expr: &Expr, params: &[f32]) -> f32 {
+        match expr {
+            Expr::Num(n) => *n,
+            Expr::Rand => (self.random)(),
+            Expr::Rank => self.rank,
+            Expr::Param(i) => params.get(i.wrapping_sub(1)).cloned().unwrap_or(0.0),
+            Expr::Neg(e) => -self.eval(e, params),
+            Expr::Op(lhs, op, rhs) => {
+                let (lhs, rhs) = (self.eval(lhs, params), self.eval(rhs, params));
+                match *op {
+                    '+' => lhs + rhs,
+                    '-' => lhs - rhs,
+                    '*' => lhs * rhs,
+                    '/' => lhs / rhs,
+                    _ => lhs % rhs,
+                }
+            }
+        }
+    }
+}
+
+impl Frame {
+    fn new(commands: Rc<Vec<Command>>, params: Rc<Vec<f32>>) -> Self {
+        Frame {
+            commands,
+            next: 0,
+            repeat: 1,
+            params,
+        }
+    }
+}
+
+impl Thread {
+    fn new(action: Rc<Vec<Command>>, params: Rc<Vec<f32>>) -> Self {
+        Thread {
+            stack: vec![Frame::new(action, params)],
+            wait: 0,
+        }
+    }
+}
+
+// the bulletml direction from `pos` to `player`
+fn aim(pos: &Vector2<f32>, player: &Vector2<f32>) -> f32 {
+    let to = player - pos;
+    to.x.atan2(-to.y).to_degrees()
+}
+
+// brings an angle in degrees into -180..180, so turns take the short way around
+fn wrap(degrees: f32) -> f32 {
+    let wrapped = (degrees + 180.0) % 360.0;
+    if wrapped < 0.0 {
+        wrapped + 180.0
+    } else {
+        wrapped - 180.0
+    }
+}
+
+// compiling elements into commands
+
+fn action(el: &Element) -> Result<Vec<Command>, String> {
+    el.children.iter().map(command).collect()
+}
+
+fn command(el: &Element) -> Result<Command, String> {
+    Ok(match el.name.as_str() {
+        "fire" => Command::Fire(Rc::new(fire(el)?)),
+        "fireRef" => Command::FireRef(label(el)?, params(el)?),
+        "changeDirection" => Command::ChangeDirection {
+            direction: amount(el.child("direction")?, Kind::Aim)?,
+            term: expr(&el.child("term")?.text)?,
+        },
+        "changeSpeed" => Command::ChangeSpeed {
+            speed: amount(el.child("speed")?, Kind::Absolute)?,
+            term: expr(&el.child("term")?.text)?,
+        },
+        "repeat" => Command::Repeat {
+            times: expr(&el.child("times")?.text)?,
+            body: Rc::new(vec![action_or_ref(el)?]),
+        },
+        "wait" => Command::Wait(expr(&el.text)?),
+        "vanish" => Command::Vanish,
+        "action" => Command::Action(Rc::new(action(el)?)),
+        "actionRef" => Command::ActionRef(label(el)?, params(el)?),
+        other => return Err(format!("unsupported element <{}>", other)),
+    })
+}
+
+fn action_or_ref(el: &Element) -> Result<Command, String> {
+    el.children
+        .iter()
+        .find(|child| child.name == "action" || child.name == "actionRef")
+        .ok_or_else(|| format!("<{}> needs an <action> or <actionRef>", el.name))
+        .and_then(command)
+}
+
+fn bullet(el: &Element) -> Result<BulletDef, String> {
+    Ok(BulletDef {
+        direction: el.opt_child("direction").map(|d| amount(d, Kind::Aim)).transpose()?,
+        speed: el.opt_child("speed").map(|s| amount(s, Kind::Absolute)).transpose()?,
+        actions: el
+            .children
+            .iter()
+            .filter(|child| child.name == "action" || child.name == "actionRef")
+            .map(|child| command(child).map(|command| Rc::new(vec![command])))
+            .collect::<Result<_, String>>()?,
+    })
+}
+
+fn fire(el: &Element) -> Result<FireDef, String> {
+    let bullet = if let Some(b) = el.opt_child("bullet") {
+        BulletSource::Inline(Rc::new(bullet(b)?))
+    } else {
+        let b = el.child("bulletRef")?;
+        BulletSource::Ref(label(b)?, params(b)?)
+    };
+
+    Ok(FireDef {
+        direction: el.opt_child("direction").map(|d| amount(d, Kind::Aim)).transpose()?,
+        speed: el.opt_child("speed").map(|s| amount(s, Kind::Absolute)).transpose()?,
+        bullet,
+    })
+}
+
+fn amount(el: &Element, default: Kind) -> Result<Amount, String> {
+    let kind = match el.attrs.get("type").map(|kind| kind.as_str()) {
+        None => default,
+        Some("aim") => Kind::Aim,
+        Some("absolute") => Kind::Absolute,
+        Some("relative") => Kind::Relative,
+        Some("sequence") => Kind::Sequence,
+        Some(other) => return Err(format!("unknown type \"{}\" on <{}>", other, el.name)),
+    };
+
+    Ok(Amount {
+        kind,
+        value: expr(&el.text)?,
+    })
+}
+
+fn label(el: &Element) -> Result<String, String> {
+    el.attrs
+        .get("label")
+        .cloned()
+        .ok_or_else(|| format!("<{}> needs a label", el.name))
+}
+
+fn params(el: &Element) -> Result<Vec<Expr>, String> {
+    el.children
+        .iter()
+        .filter(|child| child.name == "param")
+        .map(|child| expr(&child.text))
+        .collect()
+}
+
+// expressions, like "180 - $rand * 30 * $rank"
+
+fn expr(src: &str) -> Result<Expr, String> {
+    let chars = src.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
+    let mut at = 0;
+    let parsed = sum(&chars, &mut at)?;
+
+    if at < chars.len() {
+        Err(format!("unexpected '{}' in \"{}\"", chars[at], src))
+    } else {
+        Ok(parsed)
+    }
+}
+
+fn sum(chars: &[char], at: &mut usize) -> Result<Expr, String> {
+    let mut lhs = product(chars, at)?;
+    while let Some(&op) = chars.get(*at).filter(|c| "+-".contains(**c)) {
+        *at += 1;
+        lhs = Expr::Op(Box::new(lhs), op, Box::new(product(chars, at)?));
+    }
+    Ok(lhs)
+}
+
+fn product(chars: &[char], at: &mut usize) -> Result<Expr, String> {
+    let mut lhs = unary(chars, at)?;
+    while let Some(&op) = chars.get(*at).filter(|c| "*/%".contains(**c)) {
+        *at += 1;
+        lhs = Expr::Op(Box::new(lhs), op, Box::new(unary(chars, at)?));
+    }
+    Ok(lhs)
+}
+
+fn unary(chars: &[char], at: &mut usize) -> Result<Expr, String> {
+    match chars.get(*at) {
+        Some('-') => {
+            *at += 1;
+            Ok(Expr::Neg(Box::new(unary(chars, at)?)))
+        }
+        Some('+') => {
+            *at += 1;
+            unary(chars, at)
+        }
+        _ => atom(chars, at),
+    }
+}
+
+fn atom(chars: &[char], at: &mut usize) -> Result<Expr, String> {
+    let word = |at: &mut usize, f: fn(&char) -> bool| {
+        let start = *at;
+        while chars.get(*at).map_or(false, f) {
+            *at += 1;
+        }
+        chars[start..*at].iter().collect::<String>()
+    };
+
+    match chars.get(*at) {
+        Some('(') => {
+            *at += 1;
+            let inner = sum(chars, at)?;
+            if chars.get(*at) != Some(&')') {
+                return Err("missing ')'".to_string());
+            }
+            *at += 1;
+            Ok(inner)
+        }
+        Some('$') => {
+            *at += 1;
+            match word(&mut *at, |c| c.is_ascii_alphanumeric()).as_str() {
+                "rand" => Ok(Expr::Rand),
+                "rank" => Ok(Expr::Rank),
+                n => n
+                    .parse()
+                    .map(Expr::Param)
+                    .map_err(|_| format!("unknown variable ${}", n)),
+            },
+        }
+        Some(c) if c.is_ascii_digit() || *c == '.' => {
+            let n = word(&mut *at, |c| c.is_ascii_digit() || *c == '.');
+            n.parse()
+                .map(Expr::Num)
+                .map_err(|_| format!("bad number {}", n))
+        }
+        Some(c) => Err(format!("unexpected '{}'", c)),
+        None => Err("expression ended early".to_string()),
+    }
+}
+
+// just enough of an xml reader for bulletml files
+
+struct Element {
+    name: String,
+    attrs: HashMap<String, String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn opt_child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn child(&self, name: &str) -> Result<&Element, String> {
+        self.opt_child(name)
+            .ok_or_else(|| format!("<{}> needs a <{}>", self.name, name))
+    }
+}
+
+struct Reader<'a> {
+    src: &'a str,
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.at..]
+    }
+
+    // moves past the first `end` after where the reader is
+    fn skip_past(&mut self, end: &str) -> Result<&'a str, String> {
+        let rest = self.rest();
+        let len = rest.find(end).ok_or_else(|| format!("missing {}", end))?;
+        self.at += len + end.len();
+        Ok(&rest[..len])
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.at += rest.len() - rest.trim_start().len();
+    }
+
+    fn document(&mut self) -> Result<Element, String> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<!") {
+                self.skip_past(">")?;
+            } else {
+                return self.element();
+            }
+        }
+    }
+
+    fn element(&mut self) -> Result<Element, String> {
+        if !self.rest().starts_with('<') {
+            return Err("expected an element".to_string());
+        }
+        self.at += 1;
+
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .ok_or("unfinished tag")?;
+        let mut el = Element {
+            name: rest[..len].to_string(),
+            attrs: HashMap::new(),
+            children: Vec::new(),
+            text: String::new(),
+        };
+        self.at += len;
+
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.at += 2;
+                return Ok(el);
+            }
+            if self.rest().starts_with('>') {
+                self.at += 1;
+                break;
+            }
+
+            let name = self.skip_past("=")?.trim().to_string();
+            self.skip_whitespace();
+            let quote = self.rest().chars().next().ok_or("unfinished attribute")?;
+            if quote != '"' && quote != '\'' {
+                return Err(format!("unquoted attribute {}", name));
+            }
+            self.at += 1;
+            let value = unescape(self.skip_past(&quote.to_string())?);
+            el.attrs.insert(name, value);
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                let name = self.skip_past(">")?[2..].trim();
+                if name != el.name {
+                    return Err(format!("<{}> closed by </{}>", el.name, name));
+                }
+                el.text = el.text.trim().to_string();
+                return Ok(el);
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.at += "<![CDATA[".len();
+                el.text += self.skip_past("]]>")?;
+            } else if rest.starts_with('<') {
+                el.children.push(self.element()?);
+            } else if rest.is_empty() {
+                return Err(format!("<{}> is never closed", el.name));
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                el.text += &unescape(&rest[..len]);
+                self.at += len;
+            }
+        }
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runner(src: &str) -> Runner {
+        let doc = Document::parse(src).expect("couldn't parse");
+        Runner::new(Rc::new(doc), "Flower3", Cuboid::new(Vector2::new(1.0, 1.0)))
+    }
+
+    fn assert_close(a: Vector2<f32>, b: Vector2<f32>) {
+        assert!((a - b).norm() < 1e-3, "{:?} isn't {:?}", a, b);
+    }
+
+    fn step(runner: &mut Runner) -> Vec<Shot> {
+        runner.update(&Vector2::new(0.0, 0.0), &Vector2::new(0.0, 10.0))
+    }
+
+    #[test]
+    fn malformed_documents() {
+        let broken = [
+            "",
+            "<bulletml>",
+            "<bulletml><action label=\"top\"></bulletml>",
+            "<bulletml><action label=top></action></bulletml>",
+            "<notbulletml><action label=\"top\"/></notbulletml>",
+            "<bulletml><action label=\"other\"/></bulletml>",
+            "<bulletml><action label=\"top\"><wait>1 +</wait></action></bulletml>",
+            "<bulletml><action label=\"top\"><wait>$foo</wait></action></bulletml>",
+            "<bulletml><action label=\"top\"><wait>(1</wait></action></bulletml>",
+            "<bulletml><action label=\"top\"><spin/></action></bulletml>",
+            "<bulletml><action label=\"top\"><fire/></action></bulletml>",
+        ];
+        for src in broken.iter() {
+            assert!(Document::parse(src).is_err(), "{} parsed", src);
+        }
+    }
+
+    #[test]
+    fn fires_and_waits() {
+        let mut runner = runner(
+            r#"<?xml version="1.0" ?>
+            <bulletml>
+              <action label="top">
+                <repeat>
+                  <times>3</times>
+                  <action>
+                    <fire>
+                      <direction type="absolute">90</direction>
+                      <speed>2</speed>
+                      <bullet/>
+                    </fire>
+                    <wait>5</wait>
+                  </action>
+                </repeat>
+              </action>
+            </bulletml>"#,
+        );
+
+        let mut fired = Vec::new();
+        for tick in 0..30 {
+            for shot in step(&mut runner) {
+                assert_close(shot.velocity, Vector2::new(0.2, 0.0));
+                assert!(shot.script.is_none());
+                fired.push(tick);
+            }
+        }
+        assert_eq!(fired, vec![0, 5, 10]);
+        assert!(runner.threads.is_empty());
+    }
+
+    #[test]
+    fn sequences_params_and_rank() {
+        let mut runner = runner(
+            r#"<bulletml>
+              <action label="top">
+                <actionRef label="ring">
+                  <param>4</param>
+                </actionRef>
+              </action>
+              <action label="ring">
+                <repeat>
+                  <times>$1</times>
+                  <action>
+                    <fire>
+                      <direction type="sequence">90</direction>
+                      <speed>$rank * 10</speed>
+                      <bullet/>
+                    </fire>
+                  </action>
+                </repeat>
+              </action>
+            </bulletml>"#,
+        );
+        runner.rank = 0.5;
+
+        let shots = step(&mut runner);
+        let velocities = shots.iter().map(|shot| shot.velocity).collect::<Vec<_>>();
+        assert_eq!(velocities.len(), 4);
+        // starting from straight down, a quarter turn clockwise each
+        assert_close(velocities[0], Vector2::new(-0.5, 0.0));
+        assert_close(velocities[1], Vector2::new(0.0, -0.5));
+        assert_close(velocities[2], Vector2::new(0.5, 0.0));
+        assert_close(velocities[3], Vector2::new(0.0, 0.5));
+    }
+
+    #[test]
+    fn aims_and_rolls() {
+        let mut runner = runner(
+            r#"<bulletml>
+              <action label="top">
+                <fire><bullet/></fire>
+                <fire>
+                  <direction type="absolute">$rand * 360</direction>
+                  <bullet/>
+                </fire>
+              </action>
+            </bulletml>"#,
+        );
+        runner.random = || 0.25;
+
+        let shots = step(&mut runner);
+        assert_eq!(shots.len(), 2);
+        // the player is straight below
+        assert_close(shots[0].velocity, Vector2::new(0.0, 0.1));
+        assert_close(shots[1].velocity, Vector2::new(0.1, 0.0));
+    }
+
+    #[test]
+    fn changes_direction_and_speed() {
+        let mut runner = runner(
+            r#"<bulletml>
+              <action label="top">
+                <fire>
+                  <direction type="absolute">0</direction>
+                  <speed>1</speed>
+                  <bulletRef label="turn"/>
+                </fire>
+              </action>
+              <bullet label="turn">
+                <action>
+                  <changeDirection>
+                    <direction type="absolute">90</direction>
+                    <term>10</term>
+                  </changeDirection>
+                  <changeSpeed>
+                    <speed>3</speed>
+                    <term>10</term>
+                  </changeSpeed>
+                </action>
+              </bullet>
+            </bulletml>"#,
+        );
+
+        let mut shots = step(&mut runner);
+        assert_eq!(shots.len(), 1);
+        let mut bullet = shots.pop().unwrap().script.expect("bullet with no script");
+        assert_close(bullet.velocity(), Vector2::new(0.0, -0.1));
+
+        // the changes are picked up on the first tick, and spread over the next ten
+        step(&mut bullet);
+        assert_close(bullet.velocity(), Vector2::new(0.0, -0.1));
+        for _ in 0..5 {
+            step(&mut bullet);
+        }
+        let rad = 45f32.to_radians();
+        assert_close(bullet.velocity(), Vector2::new(rad.sin(), -rad.cos()) * 0.2);
+        for _ in 0..5 {
+            step(&mut bullet);
+        }
+        assert_close(bullet.velocity(), Vector2::new(0.3, 0.0));
+
+        // and then it keeps going that way
+        for _ in 0..10 {
+            step(&mut bullet);
+        }
+        assert_close(bullet.velocity(), Vector2::new(0.3, 0.0));
+        assert!(!bullet.vanished());
+    }
+
+    #[test]
+    fn moves_bullets() {
+        let mut runner = runner(
+            r#"<bulletml>
+              <action label="top">
+                <fire>
+                  <direction type="absolute">0</direction>
+                  <speed>10</speed>
+                  <bulletRef label="turn"/>
+                </fire>
+                <fire>
+                  <direction type="absolute">180</direction>
+                  <speed>5</speed>
+                  <bullet/>
+                </fire>
+              </action>
+              <bullet label="turn">
+                <action>
+                  <changeDirection>
+                    <direction type="absolute">90</direction>
+                    <term>10</term>
+                  </changeDirection>
+                  <changeSpeed>
+                    <speed>20</speed>
+                    <term>10</term>
+                  </changeSpeed>
+                </action>
+              </bullet>
+            </bulletml>"#,
+        );
+
+        // the same as the game loop does it, scripts first and then the bullets they steer
+        let mut bullets = step(&mut runner)
+            .into_iter()
+            .map(|shot| (Isometry2::identity(), Bullet::straight(shot.velocity, Kills::Good), shot.script))
+            .collect::<Vec<_>>();
+        fn tick(bullets: &mut [(Isometry2<f32>, Bullet, Option<Runner>)]) {
+            for (pos, bullet, script) in bullets.iter_mut() {
+                if let Some(script) = script.as_mut() {
+                    step(script);
+                    bullet.kind = BulletKind::Straight(script.velocity());
+                }
+                bullet.update(pos);
+            }
+        }
+
+        // a tick going straight up, then ten turning right and speeding up
+        for _ in 0..11 {
+            tick(&mut bullets);
+        }
+        let turned = bullets[0].0.translation.vector;
+        assert!(turned.x > 0.0 && turned.y < -1.0);
+
+        // and from then on it goes right at the new speed
+        for _ in 0..5 {
+            tick(&mut bullets);
+        }
+        assert_close(bullets[0].0.translation.vector - turned, Vector2::new(10.0, 0.0));
+
+        // while the bullet without a script just keeps going down
+        assert!(bullets[1].2.is_none());
+        assert_close(bullets[1].0.translation.vector, Vector2::new(0.0, 8.0));
+    }
+
+    #[test]
+    fn whirl_parses() {
+        Document::parse(include_str!("patterns/whirl.xml")).expect("couldn't parse whirl.xml");
+    }
+}
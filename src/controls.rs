@@ -30,7 +30,7 @@ impl Controls {
                     .to_lowercase()
                     .next()
                     .expect("there is no lowercase");
-                if "wsad,".contains(first_letter) {
+                if "wsad,./".contains(first_letter) {
                     keys.lock()
                         .expect("Can't lock keys")
                         .insert(first_letter, key_down);
@@ -71,7 +71,9 @@ impl Controls {
                     ('d', true) => vec + x,
                     _ => vec,
                 });
-                player_pos.translation.vector += move_vec * 0.2;
+                // holding focus slows the player down for dodging through tight gaps
+                let speed = if *keys.get(&'/').unwrap_or(&false) { 0.1 } else { 0.2 };
+                player_pos.translation.vector += move_vec * speed;
             }
 
             *keys.get(&',').unwrap_or(&false)
@@ -79,4 +81,31 @@ impl Controls {
             false
         }
     }
+
+    // whether or not the shoot key is being held down
+    pub fn shooting(&self) -> bool {
+        if let Ok(keys) = self.keys.try_lock() {
+            *keys.get(&',').unwrap_or(&false)
+        } else {
+            false
+        }
+    }
+
+    // whether or not the focus key is being held down
+    pub fn focusing(&self) -> bool {
+        if let Ok(keys) = self.keys.try_lock() {
+            *keys.get(&'/').unwrap_or(&false)
+        } else {
+            false
+        }
+    }
+
+    // whether or not the bomb key is being held down
+    pub fn bombing(&self) -> bool {
+        if let Ok(keys) = self.keys.try_lock() {
+            *keys.get(&'.').unwrap_or(&false)
+        } else {
+            false
+        }
+    }
 }
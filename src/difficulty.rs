@@ -0,0 +1,82 @@
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Lunatic,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Lunatic];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Lunatic => "Lunatic",
+        }
+    }
+
+    pub fn scale(self) -> Scale {
+        match self {
+            Difficulty::Easy => Scale::new(0.5, 0.8, 0.75),
+            Difficulty::Normal => Scale::new(1.0, 1.0, 1.0),
+            Difficulty::Hard => Scale::new(1.5, 1.15, 1.25),
+            Difficulty::Lunatic => Scale::new(2.0, 1.3, 1.5),
+        }
+    }
+
+    // what bulletml scripts see as $rank, from 0 to 1
+    pub fn rank(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.0,
+            Difficulty::Normal => 0.33,
+            Difficulty::Hard => 0.67,
+            Difficulty::Lunatic => 1.0,
+        }
+    }
+}
+
+// how much harder enemy patterns get, everything that fires reads from this
+#[derive(Clone, Copy)]
+pub struct Scale {
+    // multiplies how many bullets are fired at once
+    pub density: f32,
+    // multiplies how fast bullets go
+    pub speed: f32,
+    // multiplies how often volleys are fired
+    pub rate: f32,
+}
+
+impl Scale {
+    pub fn new(density: f32, speed: f32, rate: f32) -> Self {
+        Scale {
+            density,
+            speed,
+            rate,
+        }
+    }
+
+    // how many bullets to fire instead of `count`, never dropping a pattern to nothing
+    pub fn count(&self, count: usize) -> usize {
+        if count == 0 {
+            return 0;
+        }
+        ((count as f32 * self.density).round() as usize).max(1)
+    }
+
+    // how many ticks to wait instead of `interval`
+    pub fn interval(&self, interval: usize) -> usize {
+        ((interval as f32 / self.rate).round() as usize).max(1)
+    }
+
+    // patterns get a bit denser, faster and more frequent as rank goes from 0 to 1
+    pub fn ranked(self, rank: f32) -> Self {
+        Scale {
+            density: self.density * (1.0 + rank * 0.5),
+            speed: self.speed * (1.0 + rank * 0.2),
+            rate: self.rate * (1.0 + rank * 0.3),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use super::*;
+use super::bullet::BulletKind;
+use super::difficulty::Scale;
+use std::f32::consts::PI;
+
+#[derive(Clone)]
+pub enum Pattern {
+    // evenly spaced all the way around the emitter
+    Ring { count: usize },
+    // evenly spaced over `angle` radians, centered on the aim
+    Fan { count: usize, angle: f32 },
+    // a ring that turns `rotation` radians further after every volley
+    Spiral { count: usize, rotation: f32 },
+    // scattered randomly within `angle` radians, centered on the aim
+    Random { count: usize, angle: f32 },
+    // a fan that is centered on the player instead of the aim
+    Aimed { count: usize, angle: f32 },
+}
+
+#[derive(Clone)]
+pub struct Emitter {
+    pub pattern: Pattern,
+    pub appearance: String,
+    pub hitbox: ShapeHandle<f32>,
+    // every bullet fired is a copy of this one, sent off in its own direction
+    pub bullet: Bullet,
+    pub speed: f32,
+    // each bullet goes up to this much faster or slower than `speed`
+    pub speed_variance: f32,
+    // the direction volleys are centered on
+    pub aim: Vector2<f32>,
+    // ticks between volleys
+    pub interval: usize,
+    // ticks until the next volley
+    pub cooldown: usize,
+    // volleys left to fire, or None to keep firing forever
+    pub volleys: Option<usize>,
+    // how far a spiral has turned so far
+    turned: f32,
+}
+
+impl Emitter {
+    pub fn new<S: Into<String>, H: Shape<f32>>(
+        appearance: S,
+        hitbox: H,
+        pattern: Pattern,
+        speed: f32,
+        interval: usize,
+    ) -> Self {
+        Emitter {
+            pattern,
+            appearance: appearance.into(),
+            hitbox: ShapeHandle::new(hitbox),
+            bullet: Bullet::straight(na::zero(), Kills::Good),
+            speed,
+            speed_variance: 0.0,
+            aim: Vector2::y(),
+            interval,
+            cooldown: 0,
+            volleys: None,
+            turned: 0.0,
+        }
+    }
+
+    pub fn aim(mut self, aim: Vector2<f32>) -> Self {
+        self.aim = aim;
+        self
+    }
+
+    // ticks to wait before the first volley
+    pub fn delay(mut self, delay: usize) -> Self {
+        self.cooldown = delay;
+        self
+    }
+
+    pub fn speed_variance(mut self, variance: f32) -> Self {
+        self.speed_variance = variance;
+        self
+    }
+
+    pub fn volleys(mut self, volleys: usize) -> Self {
+        self.volleys = Some(volleys);
+        self
+    }
+
+    pub fn bullet(mut self, bullet: Bullet) -> Self {
+        self.bullet = bullet;
+        self
+    }
+
+    // counts down to the next volley,
+    // returns the bullets fired from `pos` this tick, made denser and faster by `scale`.
+    pub fn update(&mut self, pos: &Vector2<f32>, player: &Vector2<f32>, scale: &Scale) -> Vec<Bullet> {
+        if self.volleys == Some(0) {
+            return Vec::new();
+        }
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return Vec::new();
+        }
+
+        self.cooldown = scale.interval(self.interval);
+        if let Some(volleys) = self.volleys.as_mut() {
+            *volleys -= 1;
+        }
+
+        let angles = self.angles(pos, player, scale);
+        let (speed, variance) = (self.speed, self.speed_variance);
+        let template = &self.bullet;
+        angles
+            .into_iter()
+            .map(|angle| {
+                let speed = if variance > 0.0 {
+                    (speed + (random() * 2.0 - 1.0) * variance) * scale.speed
+                } else {
+                    speed * scale.speed
+                };
+                let mut bullet = template.clone();
+                bullet.kind = BulletKind::Straight(Vector2::new(angle.cos(), angle.sin()) * speed);
+                bullet
+            })
+            .collect()
+    }
+
+    fn angles(&mut self, pos: &Vector2<f32>, player: &Vector2<f32>, scale: &Scale) -> Vec<f32> {
+        use Pattern::*;
+
+        let aim = self.aim.y.atan2(self.aim.x);
+        match self.pattern {
+            Ring { count } => spread(aim, 2.0 * PI, scale.count(count), false),
+            Fan { count, angle } => spread(aim, angle, scale.count(count), true),
+            Spiral { count, rotation } => {
+                let angles = spread(aim + self.turned, 2.0 * PI, scale.count(count), false);
+                self.turned = (self.turned + rotation) % (2.0 * PI);
+                angles
+            }
+            Random { count, angle } => (0..scale.count(count))
+                .map(|_| aim + (random() - 0.5) * angle)
+                .collect(),
+            Aimed { count, angle } => {
+                let to_player = player - pos;
+                let aim = if to_player.magnitude() > 0.0 {
+                    to_player.y.atan2(to_player.x)
+                } else {
+                    aim
+                };
+                spread(aim, angle, scale.count(count), true)
+            }
+        }
+    }
+}
+
+// `count` angles spaced evenly over `angle` radians around `center`.
+// fans put a bullet on both of their edges, which a ring
+// can't do without firing two bullets in the same direction.
+pub fn spread(center: f32, angle: f32, count: usize, fan: bool) -> Vec<f32> {
+    if count <= 1 {
+        return vec![center; count];
+    }
+
+    if fan {
+        let step = angle / (count - 1) as f32;
+        (0..count).map(|i| center - angle / 2.0 + step * i as f32).collect()
+    } else {
+        let step = angle / count as f32;
+        (0..count).map(|i| center + step * i as f32).collect()
+    }
+}
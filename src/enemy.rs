@@ -1,39 +1,448 @@
 use super::*;
+use super::route::Mode;
+
+// what happened when an enemy got hit
+#[derive(Clone, Copy)]
+pub struct Damage {
+    // how much health it actually lost, after armour
+    pub dealt: usize,
+    pub killed: bool,
+}
 
 #[derive(Clone)]
 pub struct Enemy {
-    pub route: Vec<Vector2<f32>>,
+    pub route: Route,
     pub health: usize,
-    pub speed: f32,
     pub goal: usize,
+    // where the enemy started the segment it's on, and where it was before that
+    pub from: Option<Vector2<f32>>,
+    pub prev: Option<Vector2<f32>>,
+    // how far along the current segment the enemy is, from 0 to 1
+    pub progress: f32,
+    // ticks left to stand still at the node it just reached
+    pub waiting: usize,
+    // set once a route that doesn't loop has been finished
+    pub finished: bool,
+    // how far the enemy moves every tick once it's finished a route and is leaving
+    pub exit: Option<Vector2<f32>>,
+    // how much of each hit gets through, 0 makes the enemy invulnerable
+    pub armour: f32,
+    // whatever armour let through that didn't add up to a whole point of health yet
+    pub chipped: f32,
+    // pickups left behind when the enemy is shot down
+    pub drops: Vec<PickupKind>,
+    // points for shooting it down
+    pub value: usize,
 }
 
 impl Enemy {
     pub fn new(route: Vec<Vector2<f32>>, health: usize, speed: f32) -> Self {
+        Enemy::with_route(Route::straight(route, speed), health)
+    }
+
+    pub fn with_route(route: Route, health: usize) -> Self {
         Enemy {
             route,
             health,
-            speed,
             goal: 0,
+            from: None,
+            prev: None,
+            progress: 0.0,
+            waiting: 0,
+            finished: false,
+            exit: None,
+            armour: 1.0,
+            chipped: 0.0,
+            drops: Vec::new(),
+            value: 100,
         }
     }
 
+    pub fn armour(mut self, armour: f32) -> Self {
+        self.armour = armour;
+        self
+    }
+
+    pub fn worth(mut self, value: usize) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn drops(mut self, kind: PickupKind, count: usize) -> Self {
+        self.drops.extend((0..count).map(|_| kind));
+        self
+    }
+
+    // drops whatever route the enemy was on and starts on `route` from where it is now.
+    // an empty route just stands still, and counts as finished straight away.
+    pub fn follow(&mut self, route: Route) {
+        self.finished = route.nodes.is_empty();
+        self.route = route;
+        self.goal = 0;
+        self.from = None;
+        self.prev = None;
+        self.progress = 0.0;
+        self.waiting = 0;
+        self.exit = None;
+    }
+
     pub fn update(&mut self, pos: &mut Isometry2<f32>) {
-        let vec = pos.translation.vector;
-        let to_go = self.route[self.goal];
-        pos.translation.vector -= (vec - to_go).normalize() * self.speed;
+        if self.waiting > 0 {
+            self.waiting -= 1;
+            return;
+        }
+        if let Some(exit) = self.exit {
+            pos.translation.vector += exit;
+            return;
+        }
+        if self.finished || self.route.nodes.is_empty() {
+            return;
+        }
+
+        let start = pos.translation.vector;
+        // how much of this tick is left to spend moving, so that reaching a node
+        // partway through a tick carries on into the next segment instead of stopping.
+        let mut time = 1.0;
+
+        // every node could be passed in a single tick, but no more than that
+        for _ in 0..=self.route.nodes.len() {
+            // the first segment starts from wherever the enemy was put
+            let from = *self.from.get_or_insert(pos.translation.vector);
+            let prev = *self.prev.get_or_insert(from);
+            let node = &self.route.nodes[self.goal];
+
+            let speed = node.speed.unwrap_or(self.route.speed);
+            let length = self.route.length(self.goal, &prev, &from);
+            let left = (1.0 - self.progress) * length;
+            let step = speed * time;
+
+            if step < left {
+                self.progress += step / length;
+                pos.translation.vector =
+                    self.route
+                        .point_at(self.goal, &prev, &from, node.easing.apply(self.progress));
+                return;
+            }
+
+            // snap right onto the node, and keep whatever time is left over
+            pos.translation.vector = node.point;
+            if speed > 0.0 {
+                time -= left / speed;
+            }
+            self.arrive(pos.translation.vector - start, speed);
+
+            if self.waiting > 0 {
+                return;
+            }
+            if let Some(exit) = self.exit {
+                pos.translation.vector += exit * time;
+                return;
+            }
+            if self.finished {
+                return;
+            }
+        }
+    }
+
+    // moves on to the next node, once the one at `goal` has been reached.
+    // `heading` is the way the enemy was going when it got there.
+    fn arrive(&mut self, heading: Vector2<f32>, speed: f32) {
+        let node = &self.route.nodes[self.goal];
 
-        if (vec - to_go).magnitude().abs() < 0.2 {
-            self.goal += 1;
-            if self.goal >= self.route.len() {
-                self.goal = 0;
+        self.progress = 0.0;
+        self.prev = self.from;
+        self.from = Some(node.point);
+        self.waiting = node.wait;
+
+        self.goal += 1;
+        if self.goal >= self.route.nodes.len() {
+            match self.route.mode {
+                Mode::Loop => self.goal = 0,
+                Mode::PingPong => {
+                    self.route = self.route.reversed();
+                    self.goal = 1.min(self.route.nodes.len() - 1);
+                }
+                Mode::Hold => self.finished = true,
+                Mode::Exit => {
+                    let heading = if heading.magnitude() > 0.0 {
+                        heading.normalize()
+                    } else {
+                        Vector2::y()
+                    };
+                    self.finished = true;
+                    self.exit = Some(heading * speed);
+                }
             }
         }
     }
 
-    // returns a boolean indicating whether or not the player died
-    pub fn damage(&mut self) -> bool {
-        self.health = self.health.checked_sub(1).unwrap_or(0);
-        self.health == 0
+    // takes `damage` off the enemy's health, scaled by its armour.
+    // hits that armour lets only part of through add up over time.
+    pub fn damage(&mut self, damage: usize) -> Damage {
+        let through = damage as f32 * self.armour + self.chipped;
+        let dealt = (through.floor() as usize).min(self.health);
+        self.chipped = through - through.floor();
+        self.health -= dealt;
+
+        Damage {
+            dealt,
+            killed: self.health == 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a tiny xorshift, so every run tries the same routes
+    struct Rng(u32);
+
+    impl Rng {
+        fn next(&mut self) -> f32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 17;
+            self.0 ^= self.0 << 5;
+            (self.0 >> 8) as f32 / (1 << 24) as f32
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            ((self.next() * n as f32) as usize).min(n - 1)
+        }
+
+        // stopped, far too fast to see, or anything in between
+        fn speed(&mut self) -> f32 {
+            match self.below(4) {
+                0 => 0.0,
+                1 => 1e6,
+                _ => self.next() * 2.0,
+            }
+        }
+
+        // points on a coarse grid, so some of them land on top of each other
+        fn point(&mut self) -> Vector2<f32> {
+            Vector2::new(self.below(8) as f32 * 5.0, self.below(8) as f32 * 5.0)
+        }
+    }
+
+    const MODES: [Mode; 4] = [Mode::Loop, Mode::PingPong, Mode::Hold, Mode::Exit];
+    const EASINGS: [Easing; 4] = [Easing::Linear, Easing::In, Easing::Out, Easing::InOut];
+
+    // a random route, and somewhere to start it from
+    fn random_route(rng: &mut Rng, straight: bool, wait: bool) -> (Vector2<f32>, Route) {
+        let start = rng.point();
+        let mut route = Route::new(rng.speed()).mode(MODES[rng.below(4)]);
+        let mut last = start;
+
+        for _ in 0..1 + rng.below(6) {
+            // sometimes right where the last one was, including where the enemy starts
+            let point = if rng.next() < 0.3 { last } else { rng.point() };
+            route = match rng.below(if straight { 1 } else { 3 }) {
+                0 => route.to(point),
+                1 => route.curve_to(point),
+                _ => route.bezier_to(rng.point(), rng.point(), point),
+            };
+            route = route.easing(EASINGS[rng.below(4)]);
+            if rng.next() < 0.3 {
+                route = route.speed(rng.speed());
+            }
+            if wait {
+                route = route.wait(1 + rng.below(3));
+            }
+            last = point;
+        }
+
+        (start, route)
+    }
+
+    fn distance_to_segment(p: &Vector2<f32>, a: &Vector2<f32>, b: &Vector2<f32>) -> f32 {
+        let ab = b - a;
+        let t = if ab.norm_squared() > 0.0 {
+            ((p - a).dot(&ab) / ab.norm_squared()).max(0.0).min(1.0)
+        } else {
+            0.0
+        };
+        (p - (a + ab * t)).norm()
+    }
+
+    #[test]
+    fn never_nan() {
+        let mut rng = Rng(0x1234_5678);
+        for _ in 0..500 {
+            let wait = rng.next() < 0.5;
+            let (start, route) = random_route(&mut rng, false, wait);
+            let mut enemy = Enemy::with_route(route, 1);
+            let mut pos = Isometry2::translation(start.x, start.y);
+
+            for _ in 0..300 {
+                enemy.update(&mut pos);
+                let vec = pos.translation.vector;
+                assert!(vec.x.is_finite() && vec.y.is_finite(), "enemy ended up at {:?}", vec);
+            }
+        }
+    }
+
+    #[test]
+    fn stays_on_straight_routes() {
+        let mut rng = Rng(0x9e37_79b9);
+        for _ in 0..500 {
+            let wait = rng.next() < 0.5;
+            let (start, route) = random_route(&mut rng, true, wait);
+            if route.mode == Mode::Exit {
+                continue;
+            }
+
+            // every straight line the enemy could be on, between nodes or from where it started
+            let mut points = vec![start];
+            points.extend(route.nodes.iter().map(|node| node.point));
+            if route.mode == Mode::Loop {
+                points.push(route.nodes[0].point);
+            }
+
+            let mut enemy = Enemy::with_route(route, 1);
+            let mut pos = Isometry2::translation(start.x, start.y);
+            for _ in 0..300 {
+                enemy.update(&mut pos);
+                let vec = pos.translation.vector;
+                let off = points
+                    .windows(2)
+                    .map(|segment| distance_to_segment(&vec, &segment[0], &segment[1]))
+                    .fold(std::f32::INFINITY, f32::min);
+                assert!(off < 1e-3, "enemy at {:?} went {} off its route", vec, off);
+            }
+        }
+    }
+
+    #[test]
+    fn arrives_exactly_on_nodes() {
+        let mut rng = Rng(0xdead_beef);
+        let mut arrivals = 0;
+        for _ in 0..500 {
+            // with a wait on every node, the enemy stops wherever it arrives
+            let (start, route) = random_route(&mut rng, false, true);
+            let mut enemy = Enemy::with_route(route, 1);
+            let mut pos = Isometry2::translation(start.x, start.y);
+
+            for _ in 0..300 {
+                let moving = enemy.waiting == 0 && !enemy.finished;
+                let target = enemy.route.nodes[enemy.goal.min(enemy.route.nodes.len() - 1)].point;
+                enemy.update(&mut pos);
+
+                if moving && enemy.waiting > 0 {
+                    assert_eq!(pos.translation.vector, target);
+                    arrivals += 1;
+                }
+            }
+        }
+        assert!(arrivals > 0);
+    }
+
+    #[test]
+    fn armour_adds_up() {
+        // a quarter of a hit each time makes one whole point every four hits
+        let mut enemy = Enemy::new(Vec::new(), 10, 0.0).armour(0.25);
+        let dealt = (0..10).map(|_| enemy.damage(1).dealt).collect::<Vec<_>>();
+        assert_eq!(dealt, vec![0, 0, 0, 1, 0, 0, 0, 1, 0, 0]);
+        assert_eq!(enemy.health, 8);
+
+        let mut enemy = Enemy::new(Vec::new(), 10, 0.0).armour(0.0);
+        assert_eq!(enemy.damage(100).dealt, 0);
+
+        let mut enemy = Enemy::new(Vec::new(), 3, 0.0).armour(2.0);
+        let damage = enemy.damage(5);
+        assert_eq!(damage.dealt, 3);
+        assert!(damage.killed);
+    }
+
+    #[test]
+    fn spawned_on_the_first_node() {
+        let route = Route::straight(vec![Vector2::new(5.0, 5.0), Vector2::new(10.0, 5.0)], 1.0).mode(Mode::Hold);
+        let mut enemy = Enemy::with_route(route, 1);
+        let mut pos = Isometry2::translation(5.0, 5.0);
+
+        // the first node is reached straight away, and the whole tick goes toward the next
+        enemy.update(&mut pos);
+        assert_eq!(enemy.goal, 1);
+        assert!((pos.translation.vector - Vector2::new(6.0, 5.0)).norm() < 1e-5);
+    }
+
+    fn three_nodes(mode: Mode) -> Enemy {
+        let route = Route::new(0.5)
+            .to(Vector2::new(0.0, 0.0))
+            .to(Vector2::new(10.0, 0.0))
+            .to(Vector2::new(10.0, 10.0))
+            .wait(4)
+            .mode(mode);
+        let mut enemy = Enemy::with_route(route, 1);
+        enemy.goal = 2;
+        enemy.progress = 0.5;
+        enemy
+    }
+
+    #[test]
+    fn arrive_loop() {
+        let mut enemy = three_nodes(Mode::Loop);
+        enemy.arrive(Vector2::new(0.0, 1.0), 0.5);
+
+        assert_eq!(enemy.goal, 0);
+        assert_eq!(enemy.progress, 0.0);
+        assert_eq!(enemy.waiting, 4);
+        assert_eq!(enemy.from, Some(Vector2::new(10.0, 10.0)));
+        assert!(!enemy.finished);
+        assert!(enemy.exit.is_none());
+    }
+
+    #[test]
+    fn arrive_ping_pong() {
+        let mut enemy = three_nodes(Mode::PingPong);
+        enemy.arrive(Vector2::new(0.0, 1.0), 0.5);
+
+        // heads back the way it came
+        let points = enemy.route.nodes.iter().map(|node| node.point).collect::<Vec<_>>();
+        assert_eq!(
+            points,
+            vec![Vector2::new(10.0, 10.0), Vector2::new(10.0, 0.0), Vector2::new(0.0, 0.0)]
+        );
+        assert_eq!(enemy.goal, 1);
+        assert_eq!(enemy.from, Some(Vector2::new(10.0, 10.0)));
+        assert!(!enemy.finished);
+
+        // a single node has nowhere else to go
+        let route = Route::new(0.5).to(Vector2::new(3.0, 3.0)).mode(Mode::PingPong);
+        let mut enemy = Enemy::with_route(route, 1);
+        enemy.arrive(Vector2::new(0.0, 1.0), 0.5);
+        assert_eq!(enemy.goal, 0);
+    }
+
+    #[test]
+    fn arrive_hold() {
+        let mut enemy = three_nodes(Mode::Hold);
+        enemy.arrive(Vector2::new(0.0, 1.0), 0.5);
+
+        assert!(enemy.finished);
+        assert!(enemy.exit.is_none());
+        assert_eq!(enemy.from, Some(Vector2::new(10.0, 10.0)));
+
+        // and it stays put, once it's done waiting
+        let mut pos = Isometry2::translation(10.0, 10.0);
+        for _ in 0..10 {
+            enemy.update(&mut pos);
+        }
+        assert_eq!(pos.translation.vector, Vector2::new(10.0, 10.0));
+    }
+
+    #[test]
+    fn arrive_exit() {
+        let mut enemy = three_nodes(Mode::Exit);
+        enemy.arrive(Vector2::new(3.0, 4.0), 0.5);
+
+        assert!(enemy.finished);
+        let exit = enemy.exit.expect("exit route with no exit");
+        assert!((exit - Vector2::new(0.3, 0.4)).norm() < 1e-6);
+
+        // without a heading it just falls down the screen
+        let mut enemy = three_nodes(Mode::Exit);
+        enemy.arrive(Vector2::new(0.0, 0.0), 0.5);
+        assert_eq!(enemy.exit, Some(Vector2::new(0.0, 0.5)));
     }
 }
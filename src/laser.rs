@@ -0,0 +1,67 @@
+use super::bullet::BulletKills;
+use super::prelude::*;
+use nc::query::PointQuery;
+use nc::shape::Segment;
+
+// a beam that fires from its position along the local y axis,
+// so an unrotated laser points straight down the screen.
+#[derive(Clone)]
+pub struct Laser {
+    pub length: f32,
+    pub width: f32,
+    // ticks left showing where the beam will be before it can hurt anything
+    pub warning: usize,
+    // ticks left that the beam stays active for once the warning is over
+    pub active: usize,
+    // radians the beam rotates around its origin every tick
+    pub spin: f32,
+    pub kills: BulletKills,
+}
+
+impl Laser {
+    pub fn new(length: f32, width: f32, warning: usize, active: usize, kills: BulletKills) -> Self {
+        Laser {
+            length,
+            width,
+            warning,
+            active,
+            spin: 0.0,
+            kills,
+        }
+    }
+
+    pub fn spinning(mut self, spin: f32) -> Self {
+        self.spin = spin;
+        self
+    }
+
+    // advances the laser through its phases and rotates it
+    pub fn update(&mut self, pos: &mut Isometry2<f32>) {
+        pos.rotation = pos.rotation * na::UnitComplex::new(self.spin);
+
+        if self.warning > 0 {
+            self.warning -= 1;
+        } else {
+            self.active = self.active.saturating_sub(1);
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.warning == 0 && self.active > 0
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.warning == 0 && self.active == 0
+    }
+
+    // the center line of the beam, relative to the laser's position
+    pub fn segment(&self) -> Segment<f32> {
+        Segment::new(na::Point2::origin(), na::Point2::from(Vector2::y() * self.length))
+    }
+
+    // returns a boolean indicating whether or not
+    // the point is inside of the beam while it is active
+    pub fn hits(&self, pos: &Isometry2<f32>, point: &na::Point2<f32>) -> bool {
+        self.is_active() && self.segment().distance_to_point(pos, point, true) <= self.width / 2.0
+    }
+}
@@ -1,50 +1,188 @@
 #![recursion_limit = "256"]
 #[macro_use]
 extern crate stdweb;
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use stdweb::web::window;
 
 mod bullet;
 use bullet::{Bullet, BulletKills as Kills, BulletKind};
 
+mod behaviour;
+use behaviour::{Behaviour, State, Trigger};
+
+mod boss;
+use boss::{Boss, Phase};
+
+mod bulletml;
+
+mod difficulty;
+use difficulty::{Difficulty, Scale};
+
 mod controls;
 use controls::Controls;
 
+mod emitter;
+use emitter::{Emitter, Pattern};
+
 mod enemy;
 use enemy::Enemy;
 
+mod laser;
+use laser::Laser;
+
+mod parent;
+use parent::Parent;
+
+mod practice;
+use practice::Practice;
+
+mod pickup;
+use pickup::{Pickup, PickupKind};
+
 mod renderer;
 
-fn random_double() -> f64 {
+mod route;
+use route::{Easing, Mode, Route};
+
+mod satellite;
+use satellite::Satellite;
+
+mod scores;
+use scores::HighScores;
+
+mod shot;
+use shot::{Shot, ShotType, POWER_PER_LEVEL};
+
+mod stats;
+use stats::Stats;
+
+mod storage;
+use storage::Storage;
+
+thread_local! {
+    // everything random in the game comes from here, so a run started
+    // from the same seed with the same inputs plays out the same way.
+    static RANDOM: Cell<u64> = Cell::new(1);
+}
+
+// starts the game's random numbers over from `seed`
+fn seed_random(seed: u64) {
+    // xorshift never gets anywhere from 0
+    RANDOM.with(|state| state.set(seed.max(1)));
+}
+
+// a seed nobody's picked, for a run that isn't being played back
+fn new_seed() -> u64 {
     use stdweb::unstable::TryInto;
-    js! ( return Math.random(); )
+    let seed: f64 = js! ( return Math.random() * 4294967296; )
         .try_into()
-        .expect("couldn't random")
+        .expect("couldn't random");
+    seed as u64
+}
+
+fn random_double() -> f64 {
+    RANDOM.with(|state| {
+        // xorshift64*
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        (x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64
+    })
 }
 fn random() -> f32 {
     random_double() as f32
 }
 
+// pops up a box for the player to type an answer into, None if they cancel it
+fn ask(question: &str, default: &str) -> Option<String> {
+    use stdweb::unstable::TryInto;
+    js! ( return prompt(@{question}, @{default}); )
+        .try_into()
+        .unwrap_or(None)
+}
+
+// whether `pos` is on a play field of size `field`, which starts at the origin
+fn on_field(pos: &Vector2<f32>, field: &Vector2<f32>) -> bool {
+    (0..2).all(|axis| pos[axis] >= 0.0 && pos[axis] <= field[axis])
+}
+
 pub mod prelude {
     pub use na::{Isometry2, Vector2};
     pub use nalgebra as na;
-    pub use nc::shape::Cuboid;
+    pub use nc::shape::{Ball, Capsule, Cuboid, Shape, ShapeHandle};
     pub use ncollide2d as nc;
     pub use serde::{Deserialize, Serialize};
 }
 use prelude::*;
 
+// how close the player's hitbox needs to get to a pickup to collect it
+const COLLECT_RADIUS: f32 = 1.0;
+// every pickup on the screen flies to the player when they go above this line
+const AUTO_COLLECT_LINE: f32 = 8.0;
+// the most power the player can hold
+const MAX_POWER: usize = 100;
+// the most lives the player can hold, life items past that are worth points instead
+const MAX_LIVES: usize = 8;
+const EXTRA_LIFE_SCORE: usize = 5000;
+// the player gets a satellite for every power level, up to this many
+const MAX_SATELLITES: usize = 4;
+// how far away from an enemy its bullets are cancelled when it dies
+const CANCEL_RADIUS: f32 = 8.0;
+// how close a bullet has to come to the player to count as a graze
+const GRAZE_RADIUS: f32 = 2.0;
+const GRAZE_SCORE: usize = 10;
+// every this many grazes adds one to the score multiplier
+const GRAZE_PER_MULTIPLIER: f32 = 200.0;
+const MAX_MULTIPLIER: f32 = 4.0;
+// what a point item is worth when it's collected at the auto-collect line or above
+const POINT_ITEM_SCORE: usize = 1000;
+const POWER_ITEM_SCORE: usize = 10;
+const STAGE_CLEAR_SCORE: usize = 10_000;
+// rank goes all the way up after this many ticks of surviving, if nothing else moves it
+const RANK_PER_TICK: f32 = 1.0 / (60.0 * 60.0 * 10.0);
+const RANK_PER_POWER: f32 = 0.001;
+const RANK_PER_POINT: f32 = 0.000_000_1;
+const BOMB_RANK: f32 = 0.1;
+const DEATH_RANK: f32 = 0.25;
+// ticks the player can't be hit for after losing a life
+const RESPAWN_TICKS: usize = 120;
+
+// the ways a game can be started from the title screen
+const MODES: [&str; 2] = ["Game", "Practice"];
+
 pub struct Level {
+    // shown on the hud
+    pub name: &'static str,
     pub setup: Box<dyn Fn(&mut Game)>,
     pub update: Box<dyn Fn(&mut Game)>,
 }
 
 impl Level {
+    // the little dolls just drop bullets straight down on you
+    fn doll_emitter() -> Emitter {
+        Emitter::new(
+            "Flower3",
+            Cuboid::new(Vector2::new(1.0, 1.0)),
+            Pattern::Fan { count: 1, angle: 0.0 },
+            0.3,
+            8,
+        )
+        // anywhere from 0.1 to 0.5, like they used to be
+        .speed_variance(0.2)
+        .delay(10)
+    }
+
     fn third() -> Self {
         Level {
+            name: "Stage 3",
             setup: Box::new(|game: &mut Game| {
-                game.insert_enemy(
+                let doll = game.insert_enemy(
                     "Little Doll",
                     Isometry2::translation(17.5, 0.0),
                     Cuboid::new(Vector2::new(1.0, 2.5)),
@@ -55,9 +193,12 @@ impl Level {
                         Vector2::new(24.0, 25.0),
                         Vector2::new(11.0, 28.0),
                         Vector2::new(24.0, 28.0),
-                    ], 6, 0.3),
+                    ], 6, 0.3)
+                    .drops(PickupKind::Power, 3)
+                    .drops(PickupKind::Point, 2),
                 );
-                game.insert_enemy(
+                game.insert_emitter(doll, Level::doll_emitter());
+                let doll = game.insert_enemy(
                     "Little Doll",
                     Isometry2::translation(8.75, 0.0),
                     Cuboid::new(Vector2::new(1.0, 2.5)),
@@ -69,9 +210,12 @@ impl Level {
                         Vector2::new(8.0, 24.0),
                         Vector2::new(15.0, 28.0),
                        
-                    ], 6, 0.3),
+                    ], 6, 0.3)
+                    .drops(PickupKind::Power, 3)
+                    .drops(PickupKind::Point, 2),
                 );
-                game.insert_enemy(
+                game.insert_emitter(doll, Level::doll_emitter());
+                let doll = game.insert_enemy(
                     "Little Doll",
                     Isometry2::translation(26.5, 0.0),
                     Cuboid::new(Vector2::new(1.0, 2.5)),
@@ -83,38 +227,38 @@ impl Level {
                         Vector2::new(14.0, 24.0),
                         Vector2::new(19.0, 28.0),
                        
-                    ], 6, 0.3),
+                    ], 6, 0.3)
+                    .drops(PickupKind::Power, 3)
+                    .drops(PickupKind::Point, 2),
                 );
-            }),
-            update: Box::new({
-                let cooldown = Arc::new(Mutex::new(10));
-
-                move |game: &mut Game| {
-                    let mut cooldown = cooldown.lock().unwrap();
-                    if *cooldown == 0 {
-                        for i_enemy in game.enemies.clone().keys() {
-                            let enemy_pos = game.isos.get(i_enemy).expect("enemy with no pos");
+                game.insert_emitter(doll, Level::doll_emitter());
 
-                            game.insert_bullet(
-                                "Flower3",
-                                enemy_pos.clone(),
-                                Cuboid::new(Vector2::new(1.0, 1.0)),
-                                Bullet {
-                                    kind: BulletKind::Straight(
-                                        Vector2::y() * 0.4 * random() + Vector2::y() * 0.1,
-                                    ),
-                                    kills: Kills::Good,
-                                },
-                            );
-                        }
-                        *cooldown = 8;
-                    } else {
-                        *cooldown -= 1;
-                    }
-                    if game.enemies.len() == 0 {
-                        js! { alert("You survived third round.") };
-                        game.change_level(Level::fourth());
-                    }
+                // a couple of dolls that just fly across and leave,
+                // starting inside the screen so they aren't deleted before they get going
+                for &(start, end) in [(-2.0, 37.0), (34.5, -2.0)].iter() {
+                    let doll = game.insert_enemy(
+                        "Little Doll",
+                        Isometry2::translation(start, 4.0),
+                        Cuboid::new(Vector2::new(1.0, 2.5)),
+                        Enemy::with_route(
+                            Route::new(0.25)
+                                .curve_to(Vector2::new(17.5, 9.0))
+                                .curve_to(Vector2::new(end, 4.0))
+                                .mode(Mode::Exit),
+                            2,
+                        )
+                        .drops(PickupKind::Point, 3),
+                    );
+                    // firing across the screen, toward where the player starts out
+                    game.insert_emitter(
+                        doll,
+                        Level::doll_emitter().volleys(3).aim(Vector2::new(17.5 - start, 26.0)),
+                    );
+                }
+            }),
+            update: Box::new(|game: &mut Game| {
+                if game.enemies.len() == 0 {
+                    game.finish_stage("You survived third round.", Level::fourth());
                 }
             }),
         }
@@ -122,95 +266,125 @@ impl Level {
 
     fn fourth() -> Self {
         Level {
+            name: "Stage 4",
             setup: Box::new(|game: &mut Game| {
-                game.insert_enemy(
+                let doll = game.insert_enemy(
                     "Little Doll",
                     Isometry2::translation(0.0, 0.0),
                     Cuboid::new(Vector2::new(1.0, 2.5)),
-                    Enemy::new(vec![
-                        Vector2::new(0.0, 1.0),
-                        Vector2::new(0.0, 2.0),
-                        Vector2::new(2.0, 3.0),
-                        Vector2::new(4.0, 4.0),
-                        Vector2::new(6.0, 5.0),
-                        Vector2::new(8.0, 6.0),
-                        Vector2::new(10.0, 7.0),
-                        Vector2::new(12.0, 8.0),
-                        Vector2::new(14.0, 9.0),
-                        Vector2::new(16.0, 10.0),
-                        Vector2::new(18.0, 11.0),
-                        Vector2::new(20.0, 12.0),
-                        Vector2::new(22.0, 13.0),
-                        Vector2::new(24.0, 14.0),
-                        Vector2::new(26.0, 15.0),
-                        Vector2::new(28.0, 16.0),
-                        Vector2::new(30.0, 17.0),
-                        Vector2::new(32.0, 18.0),
-                        Vector2::new(30.0, 14.0),
-                        Vector2::new(28.0, 15.0),
-                        Vector2::new(26.0, 16.0),
-                        Vector2::new(24.0, 17.0),
-                        Vector2::new(22.0, 18.0),
-                        Vector2::new(20.0, 19.0),
-                        Vector2::new(18.0, 20.0),
-                        Vector2::new(16.0, 21.0),
-                        Vector2::new(14.0, 22.0),
-                        Vector2::new(12.0, 23.0),
-                        Vector2::new(10.0, 24.0),
-                        Vector2::new(8.0, 25.0),
-                        Vector2::new(6.0, 26.0),
-                        Vector2::new(4.0, 27.0),
-                        Vector2::new(6.0, 23.0),
-                        Vector2::new(8.0, 24.0),
-                        Vector2::new(10.0, 25.0),
-                        Vector2::new(12.0, 26.0),
-                        Vector2::new(14.0, 28.0),
-                        Vector2::new(16.0, 29.0),
-                        Vector2::new(18.0, 30.0),
-                        Vector2::new(20.0, 31.0),
-                        Vector2::new(22.0, 32.0),
-                        Vector2::new(24.0, 33.0),
-                        Vector2::new(26.0, 34.0),
-                        Vector2::new(28.0, 35.0),
-                    ], 20, 0.1),
+                    Enemy::with_route(
+                        Route::new(0.1)
+                            .to(Vector2::new(0.0, 2.0))
+                            .easing(Easing::Out)
+                            .curve_to(Vector2::new(32.0, 18.0))
+                            // a quicker swoop back into the middle
+                            .bezier_to(Vector2::new(34.0, 26.0), Vector2::new(24.0, 12.0), Vector2::new(18.0, 20.0))
+                            .speed(0.2)
+                            .curve_to(Vector2::new(4.0, 27.0))
+                            .easing(Easing::In)
+                            .wait(30)
+                            .curve_to(Vector2::new(28.0, 35.0))
+                            .easing(Easing::InOut),
+                        20,
+                    )
+                    .drops(PickupKind::Life, 1)
+                    .drops(PickupKind::Power, 5),
                 );
-            }),
-            update: Box::new({
-                let cooldown = Arc::new(Mutex::new(10));
+                game.insert_emitter(
+                    doll,
+                    Emitter::new(
+                        "Syringe",
+                        Capsule::new(0.8, 0.3),
+                        Pattern::Random { count: 10, angle: 2.0 * PI },
+                        0.2,
+                        20,
+                    )
+                    .delay(10)
+                    // they fade out partway across the screen, so the field doesn't fill up
+                    .bullet(
+                        Bullet::straight(na::zero(), Kills::Good)
+                            .oriented()
+                            .delayed(20)
+                            .lifetime(100),
+                    ),
+                );
+                // a few bullets circling it, that go away along with it
+                for i in 0..4 {
+                    let angle = PI / 2.0 * i as f32;
+                    let orbiter = game.insert_bullet(
+                        "Flower3",
+                        Isometry2::identity(),
+                        Cuboid::new(Vector2::new(1.0, 1.0)),
+                        Bullet::straight(na::zero(), Kills::Good),
+                    );
+                    game.insert_parent(
+                        orbiter,
+                        Parent::new(doll, Isometry2::new(Vector2::new(angle.cos(), angle.sin()) * 4.0, 0.0))
+                            .spinning(0.04),
+                    );
+                }
 
-                move |game: &mut Game| {
-                    let mut cooldown = cooldown.lock().unwrap();
-                    if *cooldown == 0 {
-                        for i_enemy in game.enemies.clone().keys() {
-                            let enemy_pos = game.isos.get(i_enemy).expect("enemy with no pos").clone();
+                // a doll that keeps watch, shoots when you come close, and runs when it's hurt
+                let guard = game.insert_enemy(
+                    "Little Doll",
+                    Isometry2::translation(30.0, -2.0),
+                    Cuboid::new(Vector2::new(1.0, 2.5)),
+                    Enemy::new(Vec::new(), 8, 0.0).worth(500).drops(PickupKind::Bomb, 1),
+                );
+                game.insert_emitter(
+                    guard,
+                    Emitter::new(
+                        "Flower3",
+                        Cuboid::new(Vector2::new(1.0, 1.0)),
+                        Pattern::Aimed { count: 3, angle: 0.4 },
+                        0.4,
+                        15,
+                    ),
+                );
+                game.insert_behaviour(
+                    guard,
+                    Behaviour::new()
+                        .route(State::Enter, Route::new(0.3).to(Vector2::new(30.0, 6.0)).mode(Mode::Hold))
+                        .route(
+                            State::Patrol,
+                            Route::straight(vec![Vector2::new(22.0, 6.0), Vector2::new(30.0, 6.0)], 0.15),
+                        )
+                        .route(State::Retreat, Route::new(0.3).to(Vector2::new(30.0, 3.0)).mode(Mode::Hold))
+                        .on(State::Enter, Trigger::Arrived, State::Patrol)
+                        .on(State::Patrol, Trigger::Health(3), State::Flee)
+                        .on(State::Patrol, Trigger::Near(15.0), State::Attack)
+                        .on(State::Attack, Trigger::Health(3), State::Flee)
+                        // it gives up once the player gets away from it
+                        .on(State::Attack, Trigger::Far(20.0), State::Patrol)
+                        .on(State::Attack, Trigger::After(90), State::Retreat)
+                        .on(State::Retreat, Trigger::Health(3), State::Flee)
+                        .on(State::Retreat, Trigger::After(60), State::Patrol)
+                        .flee_speed(0.8),
+                );
 
-                            for i in 0..10 {
-                                game.insert_bullet(
-                                    "Flower3",
-                                    enemy_pos.clone(),
-                                    Cuboid::new(Vector2::new(1.0, 1.0)),
-                                    Bullet {
-                                        kind: BulletKind::Straight(
-                                            Vector2::new(
-                                                1.0 - random() * 2.0,
-                                                1.0 - random() * 2.0,
-                                                )
-                                                .normalize()
-                                                * 0.2,
-                                        ),
-                                        kills: Kills::Good,
-                                    },
-                                );
-                            }
-                        }
-                        *cooldown = 20;
-                    } else {
-                        *cooldown -= 1;
-                    }
-                    if game.enemies.len() == 0 {
-                        js! { alert("You survived the fourth round.") };
-                        game.change_level(Level::fifth());
-                    }
+                // a doll that comes down the middle and fires a bulletml pattern
+                let whirl = Rc::new(
+                    bulletml::Document::parse(include_str!("patterns/whirl.xml")).expect("bad whirl.xml"),
+                );
+                let doll = game.insert_enemy(
+                    "Little Doll",
+                    Isometry2::translation(17.5, -2.0),
+                    Cuboid::new(Vector2::new(1.0, 2.5)),
+                    // armoured, so it takes a while to bring down
+                    Enemy::with_route(Route::new(0.2).to(Vector2::new(17.5, 8.0)).mode(Mode::Hold), 12)
+                        .armour(0.5)
+                        .worth(1000)
+                        .drops(PickupKind::Power, 3)
+                        .drops(PickupKind::Point, 3),
+                );
+                game.insert_script(
+                    doll,
+                    bulletml::Runner::new(whirl, "Flower3", Cuboid::new(Vector2::new(1.0, 1.0))),
+                );
+            }),
+            update: Box::new(|game: &mut Game| {
+                if game.enemies.len() == 0 {
+                    game.finish_stage("You survived the fourth round.", Level::fifth());
                 }
             }),
         }
@@ -218,51 +392,83 @@ impl Level {
 
     fn fifth() -> Self {
         Level {
+            name: "Final Stage",
             setup: Box::new(|game: &mut Game| {
-                game.insert_enemy(
+                game.insert_boss(
                     "Eye",
                     Isometry2::translation(17.5, 5.0),
                     Cuboid::new(Vector2::new(1.0, 2.5)),
-                    Enemy::new(
-                        vec![Vector2::new(17.5, 4.0), Vector2::new(17.5, 6.0)],
-                        6,
-                        0.3,
-                    ),
-                );
-            }),
-            update: Box::new({
-                let cooldown = Arc::new(Mutex::new(10));
-
-                move |game: &mut Game| {
-                    let mut cooldown = cooldown.lock().unwrap();
-                    if *cooldown == 0 {
-                        for i_enemy in game.enemies.clone().keys() {
-                            let enemy_pos = game.isos.get(i_enemy).expect("enemy with no pos").clone();
-
-                            for i in 0..20 {
-                                game.insert_bullet(
+                    Boss::new(vec![
+                        Phase::new(
+                            6,
+                            Route::straight(
+                                vec![Vector2::new(17.5, 4.0), Vector2::new(17.5, 6.0)],
+                                0.3,
+                            ),
+                            vec![Emitter::new(
+                                "Flower3",
+                                Cuboid::new(Vector2::new(1.0, 1.0)),
+                                Pattern::Random { count: 20, angle: PI },
+                                0.6,
+                                8,
+                            )
+                            .delay(10)],
+                            30 * 60,
+                        ),
+                        Phase::new(
+                            10,
+                            Route::new(0.15)
+                                .curve_to(Vector2::new(8.0, 6.0))
+                                .curve_to(Vector2::new(27.0, 6.0))
+                                .mode(Mode::PingPong),
+                            vec![Emitter::new(
+                                "Flower3",
+                                Cuboid::new(Vector2::new(1.0, 1.0)),
+                                Pattern::Spiral { count: 6, rotation: 0.2 },
+                                0.3,
+                                4,
+                            )
+                            .delay(30)],
+                            30 * 60,
+                        )
+                        .spell_card("Eye Sign \"Wandering Gaze\"", 5000),
+                        Phase::new(
+                            12,
+                            Route::new(0.3).to(Vector2::new(17.5, 8.0)).mode(Mode::Hold),
+                            vec![
+                                Emitter::new(
                                     "Flower3",
-                                    enemy_pos.clone(),
                                     Cuboid::new(Vector2::new(1.0, 1.0)),
-                                    Bullet {
-                                        kind: BulletKind::Straight(
-                                            Vector2::new(1.0 - random() * 2.0, random())
-                                                .normalize()
-                                                * 0.6,
-                                        ),
-                                        kills: Kills::Good,
-                                    },
-                                );
-                            }
-                        }
-                        *cooldown = 8;
-                    } else {
-                        *cooldown -= 1;
-                    }
-                    if game.enemies.len() == 0 {
-                        js! { alert("You survived final round.") };
-                        game.change_level(Level::second());
-                    }
+                                    Pattern::Ring { count: 16 },
+                                    0.25,
+                                    40,
+                                )
+                                .delay(30),
+                                Emitter::new(
+                                    "Syringe",
+                                    Capsule::new(0.8, 0.3),
+                                    Pattern::Aimed { count: 3, angle: 0.5 },
+                                    0.5,
+                                    25,
+                                )
+                                .delay(45)
+                                .bullet(Bullet::straight(na::zero(), Kills::Good).oriented()),
+                            ],
+                            40 * 60,
+                        )
+                        .spell_card("Syringe Sign \"Bedside Manner\"", 10000),
+                    ]),
+                );
+                // a beam that sweeps slowly across the bottom of the screen
+                game.insert_laser(
+                    "Laser",
+                    Isometry2::new(Vector2::new(17.5, 5.0), 1.0),
+                    Laser::new(40.0, 1.0, 60, 200, Kills::Good).spinning(-0.01),
+                );
+            }),
+            update: Box::new(|game: &mut Game| {
+                if game.enemies.len() == 0 {
+                    game.finish_stage("You survived final round.", Level::second());
                 }
             }),
         }
@@ -270,75 +476,154 @@ impl Level {
 
     fn second() -> Self {
         Level {
+            name: "Stage 2",
             setup: Box::new(|game: &mut Game| {
-                for i in 0..40 {
+                let scale = game.scale();
+                let field = game.field();
+                let count = scale.count(40);
+                for i in 0..count {
                     game.insert_bullet(
                         "Flower3",
-                        Isometry2::translation(0.0, (i as f32) * 1.0),
+                        // spread down the left edge however many there are
+                        Isometry2::translation(0.0, (i as f32) * field.y / count as f32),
                         Cuboid::new(Vector2::new(1.0, 1.0)),
-                        Bullet {
-                            kind: BulletKind::Straight(
-                                Vector2::x() * 0.4 * random() + Vector2::x() * 0.1,
-                            ),
-                            kills: Kills::Good,
-                        },
+                        // they come back once off the right edge
+                        Bullet::straight(
+                            (Vector2::x() * 0.4 * random() + Vector2::x() * 0.1) * scale.speed,
+                            Kills::Good,
+                        )
+                        .bouncing(1),
                     );
                 }
             }),
             update: Box::new(|game: &mut Game| {
                 if game.bullets.len() == 0 {
-                    js! { alert("You survived second round.") };
-                    game.change_level(Level::third());
+                    game.finish_stage("You survived second round.", Level::third());
                 }
             }),
         }
     }
 
+    // pick a difficulty and a shot type by standing on them and shooting
+    fn title() -> Self {
+        Level {
+            name: "Title",
+            setup: Box::new(|game: &mut Game| {
+                let hint = game.entity();
+                game.isos.insert(hint, Isometry2::translation(17.5, 6.0));
+                game.labels.insert(hint, "stand on a difficulty and mode, then a shot type, and press ,".to_string());
+
+                for (i, difficulty) in Difficulty::ALL.iter().enumerate() {
+                    let choice = game.entity();
+                    game.isos.insert(choice, Isometry2::translation(5.0 + 8.5 * i as f32, 12.0));
+                    game.labels.insert(choice, difficulty.name().to_string());
+                }
+                for (i, mode) in MODES.iter().enumerate() {
+                    let choice = game.entity();
+                    game.isos.insert(choice, Isometry2::translation(12.0 + 11.0 * i as f32, 18.0));
+                    game.labels.insert(choice, mode.to_string());
+                }
+                for (i, shot_type) in ShotType::ALL.iter().enumerate() {
+                    let choice = game.entity();
+                    game.isos.insert(choice, Isometry2::translation(7.0 + 10.5 * i as f32, 24.0));
+                    game.labels.insert(choice, shot_type.name().to_string());
+                }
+                let difficulty = game.difficulty;
+                game.mark_choice(&Difficulty::ALL.iter().map(|d| d.name()).collect::<Vec<_>>(), difficulty.name());
+                game.mark_choice(&MODES, MODES[0]);
+            }),
+            update: Box::new(|game: &mut Game| {
+                if !game.controls.shooting() {
+                    return;
+                }
+
+                // whichever label the player is standing closest to, if they're near enough to one
+                let player = game.isos[&game.player].translation.vector;
+                let chosen = game
+                    .labels
+                    .iter()
+                    .map(|(i, label)| {
+                        let name = label.trim_matches(|c| c == '[' || c == ']').to_string();
+                        (name, game.isos[i].translation.vector)
+                    })
+                    .filter(|(_, pos)| (pos - player).magnitude() < 3.0)
+                    .min_by(|(_, a), (_, b)| {
+                        (a - player)
+                            .magnitude()
+                            .partial_cmp(&(b - player).magnitude())
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                    })
+                    .map(|(label, _)| label);
+                let chosen = match chosen {
+                    Some(chosen) => chosen,
+                    None => return,
+                };
+
+                if let Some(difficulty) = Difficulty::ALL.iter().find(|d| d.name() == chosen) {
+                    game.difficulty = *difficulty;
+                    game.mark_choice(&Difficulty::ALL.iter().map(|d| d.name()).collect::<Vec<_>>(), difficulty.name());
+                }
+                if let Some(mode) = MODES.iter().find(|mode| **mode == chosen) {
+                    game.mark_choice(&MODES, mode);
+                }
+                if let Some(shot_type) = ShotType::ALL.iter().find(|t| t.name() == chosen) {
+                    // the mode picked is whichever one has brackets around it
+                    let practicing = game.labels.values().any(|label| *label == format!("[{}]", MODES[1]));
+
+                    let labels = game.labels.keys().cloned().collect::<Vec<_>>();
+                    for label in labels.iter() {
+                        game.delete_ent(label);
+                    }
+                    game.choose_shot(*shot_type);
+                    // time spent on the title screen doesn't count
+                    game.rank = 0.0;
+                    game.reseed();
+
+                    if practicing {
+                        game.start_practice(Practice::ask());
+                    } else {
+                        game.change_level(Level::first());
+                    }
+                }
+            }),
+        }
+    }
+
+    // every stage in the order they're played, for practicing them one at a time
+    fn stages() -> Vec<fn() -> Level> {
+        vec![Level::first, Level::second, Level::third, Level::fourth, Level::fifth]
+    }
+
     fn first() -> Self {
         Level {
+            name: "Stage 1",
             setup: Box::new(|game: &mut Game| {
-                game.insert_enemy(
+                let eye = game.insert_enemy(
                     "Eye",
                     Isometry2::translation(17.5, 5.0),
                     Cuboid::new(Vector2::new(1.0, 2.5)),
                     Enemy::new(vec![
                         Vector2::new(17.5, 4.0),
                         Vector2::new(17.5, 6.0),
-                    ], 6, 0.3),
+                    ], 6, 0.3)
+                    .drops(PickupKind::Power, 3)
+                    .drops(PickupKind::Point, 2),
+                );
+                game.insert_emitter(
+                    eye,
+                    Emitter::new(
+                        "Flower3",
+                        Cuboid::new(Vector2::new(1.0, 1.0)),
+                        Pattern::Random { count: 1, angle: PI },
+                        0.8,
+                        12,
+                    )
+                    .delay(10),
                 );
             }),
-            update: Box::new({
-                let cooldown = Arc::new(Mutex::new(10));
-
-                move |game: &mut Game| {
-                    let mut cooldown = cooldown.lock().unwrap();
-                    if *cooldown == 0 {
-                        for i_enemy in game.enemies.clone().keys() {
-                            let enemy_pos = game.isos.get(i_enemy).expect("enemy with no pos");
-
-                            game.insert_bullet(
-                                "Flower3",
-                                enemy_pos.clone(),
-                                Cuboid::new(Vector2::new(1.0, 1.0)),
-                                Bullet {
-                                    kind: BulletKind::Straight(
-                                        Vector2::new(
-                                            1.0 - random() * 2.0,
-                                            random(),
-                                        ).normalize() * 0.8
-                                    ),
-                                    kills: Kills::Good,
-                                },
-                            );
-                        }
-                        *cooldown = 12;
-                    } else {
-                        *cooldown -= 1;
-                    }
-                    if game.enemies.len() == 0 {
-                        js! { alert("You survived first round.") };
-                        game.change_level(Level::second());
-                    }
+            update: Box::new(|game: &mut Game| {
+                if game.enemies.len() == 0 {
+                    game.finish_stage("You survived first round.", Level::second());
                 }
             }),
         }
@@ -350,12 +635,52 @@ pub struct Game {
     pub isos: HashMap<usize, Isometry2<f32>>,
     pub bullets: HashMap<usize, Bullet>,
     pub enemies: HashMap<usize, Enemy>,
-    pub hitboxes: HashMap<usize, Cuboid<f32>>,
+    pub lasers: HashMap<usize, Laser>,
+    pub emitters: HashMap<usize, Vec<Emitter>>,
+    pub scripts: HashMap<usize, bulletml::Runner>,
+    pub pickups: HashMap<usize, Pickup>,
+    pub bosses: HashMap<usize, Boss>,
+    pub behaviours: HashMap<usize, Behaviour>,
+    pub parents: HashMap<usize, Parent>,
+    pub satellites: HashMap<usize, Satellite>,
+    pub hitboxes: HashMap<usize, ShapeHandle<f32>>,
     pub appearances: HashMap<usize, String>,
+    // text drawn where the entity is, for menus
+    pub labels: HashMap<usize, String>,
 
     // controls
     pub controls: Controls,
     pub shooting_cooldown: usize,
+    pub shot_type: ShotType,
+    pub shot: Shot,
+    pub bombs: usize,
+    pub bomb_cooldown: usize,
+
+    // point items picked up
+    pub point_items: usize,
+    pub power: usize,
+    pub lives: usize,
+    // bullets that came close to the player without hitting them
+    pub graze: usize,
+    pub score: usize,
+    pub hi_score: usize,
+    // enemies shot down, not counting the ones that just flew away
+    pub kills: usize,
+    // kept between sessions
+    pub storage: Box<dyn Storage>,
+    pub stats: Stats,
+    // the table for the difficulty and shot type being played
+    pub high_scores: HighScores,
+    pub difficulty: Difficulty,
+    // a hidden 0 to 1 value that makes patterns harder while the player is doing well.
+    // it only moves with things that happen in the game, so replays get the same rank.
+    pub rank: f32,
+    // what this run's random numbers started from, which a replay needs along with the inputs
+    pub seed: u64,
+    // set while practicing, which never ends the game
+    pub practice: Option<Practice>,
+    // ticks left that the player can't be hit for
+    pub invulnerable: usize,
 
     // screen size
     pub screen: Cuboid<f32>,
@@ -370,21 +695,52 @@ pub struct Game {
 
 impl Game {
     pub fn new(level: Level) -> Self {
+        let storage = storage::default_storage();
         let mut new_game = Self {
             // components
             isos: HashMap::new(),
             bullets: HashMap::new(),
             enemies: HashMap::new(),
+            lasers: HashMap::new(),
+            emitters: HashMap::new(),
+            scripts: HashMap::new(),
+            pickups: HashMap::new(),
+            bosses: HashMap::new(),
+            behaviours: HashMap::new(),
+            parents: HashMap::new(),
+            satellites: HashMap::new(),
             hitboxes: HashMap::new(),
             appearances: HashMap::new(),
+            labels: HashMap::new(),
 
             // controls
             controls: Controls::default(),
             shooting_cooldown: 0,
+            shot_type: ShotType::Spread,
+            shot: ShotType::Spread.shot(),
+            bombs: 3,
+            bomb_cooldown: 0,
+
+            point_items: 0,
+            power: 0,
+            lives: 2,
+            graze: 0,
+            score: 0,
+            hi_score: 0,
+            kills: 0,
+            stats: Stats::load(&*storage),
+            high_scores: HighScores::load(&*storage, Difficulty::Normal, ShotType::Spread),
+            storage,
+            difficulty: Difficulty::Normal,
+            rank: 0.0,
+            seed: 0,
+            practice: None,
+            invulnerable: 0,
 
             // // state
             // dumb level to override
             level: Arc::new(Mutex::new(Level {
+                name: "",
                 setup: Box::new(|_: &mut Game| {}),
                 update: Box::new(|_: &mut Game| {}),
             })),
@@ -396,19 +752,46 @@ impl Game {
             player: 0,
             ent_count: 0,
         };
+        new_game.hi_score = new_game.high_scores.best();
         new_game.change_level(level);
         new_game
     }
 
+    // deleting an entity takes everything attached to it down too
     pub fn delete_ent(&mut self, kill: &usize) {
+        let children = self
+            .parents
+            .iter()
+            .filter(|(_, parent)| parent.ent == *kill)
+            .map(|(i_child, _)| *i_child)
+            .collect::<Vec<_>>();
+        for child in children.iter() {
+            self.parents.remove(child);
+            self.delete_ent(child);
+        }
+
         self.isos.remove(kill);
         self.bullets.remove(kill);
         self.enemies.remove(kill);
+        self.lasers.remove(kill);
+        self.emitters.remove(kill);
+        self.scripts.remove(kill);
+        self.pickups.remove(kill);
+        self.bosses.remove(kill);
+        self.behaviours.remove(kill);
+        self.parents.remove(kill);
+        self.satellites.remove(kill);
         self.hitboxes.remove(kill);
         self.appearances.remove(kill);
+        self.labels.remove(kill);
     }
 
-    pub fn insert_bullet<S: Into<String>>(&mut self, appearance: S, iso: Isometry2<f32>, hb: Cuboid<f32>, bullet: Bullet) -> usize {
+    pub fn insert_bullet<S: Into<String>, H: Shape<f32>>(&mut self, appearance: S, iso: Isometry2<f32>, hb: H, bullet: Bullet) -> usize {
+        self.insert_bullet_handle(appearance, iso, ShapeHandle::new(hb), bullet)
+    }
+
+    // for when the hitbox is already shared with something else, like an emitter
+    pub fn insert_bullet_handle<S: Into<String>>(&mut self, appearance: S, iso: Isometry2<f32>, hb: ShapeHandle<f32>, bullet: Bullet) -> usize {
         let ent = self.entity();
 
         self.isos.insert(ent, iso);
@@ -419,16 +802,466 @@ impl Game {
         ent
     }
 
-    pub fn insert_enemy<S: Into<String>>(&mut self, appearance: S, iso: Isometry2<f32>, hb: Cuboid<f32>, enemy: Enemy) -> usize {
+    pub fn insert_enemy<S: Into<String>, H: Shape<f32>>(&mut self, appearance: S, iso: Isometry2<f32>, hb: H, enemy: Enemy) -> usize {
         let ent = self.entity();
 
         self.isos.insert(ent, iso);
         self.enemies.insert(ent, enemy);
-        self.hitboxes.insert(ent, hb);
+        self.hitboxes.insert(ent, ShapeHandle::new(hb));
+        self.appearances.insert(ent, appearance.into());
+
+        ent
+    }
+
+    // bosses are enemies that start out running their first phase
+    pub fn insert_boss<S: Into<String>, H: Shape<f32>>(&mut self, appearance: S, iso: Isometry2<f32>, hb: H, boss: Boss) -> usize {
+        let phase = boss.current().clone();
+        let ent = self.insert_enemy(appearance, iso, hb, Enemy::with_route(phase.route, phase.health));
+
+        self.bosses.insert(ent, boss);
+        self.emitters.insert(ent, phase.emitters);
+        self.start_attack(ent);
+
+        ent
+    }
+
+    // moves a boss on to its next phase, turning every bullet on the screen into points.
+    // returns a boolean indicating whether or not the boss had any phases left.
+    pub fn next_phase(&mut self, ent: usize) -> bool {
+        let phase = match self.bosses.get_mut(&ent).and_then(|boss| boss.next_phase()) {
+            Some(phase) => phase.clone(),
+            None => return false,
+        };
+
+        // starting a new route from wherever the boss is now
+        self.enemies.insert(ent, Enemy::with_route(phase.route, phase.health));
+        self.emitters.insert(ent, phase.emitters);
+        self.start_attack(ent);
+
+        if let Some(pos) = self.isos.get(&ent).map(|iso| iso.translation.vector) {
+            self.cancel_bullets(&pos, std::f32::INFINITY);
+        }
+
+        true
+    }
+
+    // puts a boss straight onto one of its phases, without clearing the screen like beating one does
+    pub fn skip_to_phase(&mut self, ent: usize, phase: usize) {
+        let phase = match self.bosses.get_mut(&ent) {
+            Some(boss) => {
+                boss.skip_to(phase);
+                boss.current().clone()
+            }
+            None => return,
+        };
+
+        self.enemies.insert(ent, Enemy::with_route(phase.route, phase.health));
+        self.emitters.insert(ent, phase.emitters);
+        self.start_attack(ent);
+    }
+
+    // counts another try at the boss's current spell card, if it's using one
+    fn start_attack(&mut self, ent: usize) {
+        if let Some(name) = self.bosses.get(&ent).and_then(|boss| boss.current().name.clone()) {
+            self.stats.attempt(&name);
+            self.stats.save(&mut *self.storage);
+        }
+    }
+
+    // hands out the capture bonus when a spell card is beaten
+    // without the player dying or bombing. running out the clock doesn't count.
+    pub fn end_attack(&mut self, ent: usize, beaten: bool) {
+        let boss = match self.bosses.get(&ent) {
+            Some(boss) => boss,
+            None => return,
+        };
+        let phase = boss.current();
+
+        if let Some(name) = &phase.name {
+            if beaten && boss.capturing {
+                self.add_score(phase.bonus);
+                self.stats.capture(name);
+                self.stats.save(&mut *self.storage);
+            }
+        }
+    }
+
+    // the player slipped up, so no spell card on the screen can be captured anymore
+    pub fn fail_attacks(&mut self) {
+        for boss in self.bosses.values_mut() {
+            boss.capturing = false;
+        }
+    }
+
+    // enemies with a behaviour pick their own routes, and only fire while attacking
+    pub fn insert_behaviour(&mut self, ent: usize, behaviour: Behaviour) {
+        if let Some(enemy) = self.enemies.get_mut(&ent) {
+            behaviour.start(enemy);
+        }
+        self.behaviours.insert(ent, behaviour);
+    }
+
+    // an entity can have any number of emitters, they all fire independently
+    pub fn insert_emitter(&mut self, ent: usize, emitter: Emitter) {
+        self.emitters.entry(ent).or_insert_with(Vec::new).push(emitter);
+    }
+
+    // a bulletml script fires from the entity it's attached to,
+    // and steers it too if the entity happens to be a bullet.
+    pub fn insert_script(&mut self, ent: usize, script: bulletml::Runner) {
+        self.scripts.insert(ent, script);
+    }
+
+    pub fn insert_pickup<S: Into<String>, H: Shape<f32>>(&mut self, appearance: S, iso: Isometry2<f32>, hb: H, pickup: Pickup) -> usize {
+        let ent = self.entity();
+
+        self.isos.insert(ent, iso);
+        self.pickups.insert(ent, pickup);
+        self.hitboxes.insert(ent, ShapeHandle::new(hb));
+        self.appearances.insert(ent, appearance.into());
+
+        ent
+    }
+
+    // turns the bullets that could kill the player within `radius` of `center`
+    // into point items that fly straight at the player.
+    pub fn cancel_bullets(&mut self, center: &Vector2<f32>, radius: f32) {
+        let cancelled = self
+            .bullets
+            .iter()
+            .filter(|(_, bullet)| match bullet.kills {
+                Kills::Good => true,
+                Kills::Bad => false,
+            })
+            .map(|(i_bullet, _)| *i_bullet)
+            .filter(|i_bullet| (self.isos[i_bullet].translation.vector - center).magnitude() <= radius)
+            .collect::<Vec<_>>();
+
+        for i_bullet in cancelled {
+            // bullets attached to one that was cancelled already went along with it
+            let pos = match self.isos.get(&i_bullet) {
+                Some(pos) => pos.translation.vector,
+                None => continue,
+            };
+            self.delete_ent(&i_bullet);
+            self.insert_pickup(
+                "Point",
+                Isometry2::new(pos, 0.0),
+                Ball::new(0.5),
+                Pickup::homing(PickupKind::Point),
+            );
+        }
+    }
+
+    // `pos` is where the pickup was when the player got it
+    pub fn collect(&mut self, pickup: &Pickup, pos: &Vector2<f32>) {
+        match pickup.kind {
+            PickupKind::Point => {
+                self.point_items += 1;
+                // worth less the further down the screen it was caught,
+                // unless it was already flying to the player by itself.
+                let bottom = self.screen.half_extents().y;
+                let height = if pickup.homing {
+                    1.0
+                } else {
+                    1.0 - ((pos.y - AUTO_COLLECT_LINE) / (bottom - AUTO_COLLECT_LINE)).max(0.0).min(0.9)
+                };
+                self.add_score((POINT_ITEM_SCORE as f32 * height) as usize);
+            }
+            PickupKind::Power => {
+                self.power = (self.power + 1).min(MAX_POWER);
+                self.change_rank(RANK_PER_POWER);
+                self.add_score(POWER_ITEM_SCORE);
+            }
+            PickupKind::Life if self.lives < MAX_LIVES => self.lives += 1,
+            PickupKind::Life => self.add_score(EXTRA_LIFE_SCORE),
+            PickupKind::Bomb => self.bombs += 1,
+        }
+    }
+
+    // grazing builds up a multiplier on all the points the player earns
+    pub fn multiplier(&self) -> f32 {
+        (1.0 + self.graze as f32 / GRAZE_PER_MULTIPLIER).min(MAX_MULTIPLIER)
+    }
+
+    pub fn add_score(&mut self, points: usize) {
+        self.score += (points as f32 * self.multiplier()) as usize;
+        self.change_rank(points as f32 * RANK_PER_POINT);
+        self.hi_score = self.hi_score.max(self.score);
+    }
+
+    // a bonus for getting through a level, which goes up the more lives and bombs are left
+    pub fn clear_stage(&mut self) {
+        self.add_score(STAGE_CLEAR_SCORE * (1 + self.lives + self.bombs));
+    }
+
+    // scatters an enemy's drops around where it died
+    pub fn drop_items(&mut self, ent: usize) {
+        let drops = match self.enemies.get_mut(&ent) {
+            Some(enemy) => std::mem::replace(&mut enemy.drops, Vec::new()),
+            None => return,
+        };
+        let pos = self.isos.get(&ent).expect("enemy with no pos").translation.vector;
+
+        for kind in drops {
+            let offset = Vector2::new(random() - 0.5, random() - 0.5) * 3.0;
+            let appearance = match kind {
+                PickupKind::Point => "Point",
+                PickupKind::Power => "Power",
+                PickupKind::Life => "Life",
+                PickupKind::Bomb => "Bomb",
+            };
+            self.insert_pickup(
+                appearance,
+                Isometry2::new(pos + offset, 0.0),
+                Ball::new(0.5),
+                Pickup::new(kind),
+            );
+        }
+    }
+
+    // lasers don't get a hitbox, they're tested along their whole beam instead
+    pub fn insert_laser<S: Into<String>>(&mut self, appearance: S, iso: Isometry2<f32>, laser: Laser) -> usize {
+        let ent = self.entity();
+
+        self.isos.insert(ent, iso);
+        self.lasers.insert(ent, laser);
         self.appearances.insert(ent, appearance.into());
 
         ent
     }
+
+    // the size of the play field the renderer draws, which starts at the origin
+    // and so only covers the positive quarter of `screen`
+    pub fn field(&self) -> Vector2<f32> {
+        *self.screen.half_extents()
+    }
+
+    // starts a run's random numbers from a new seed
+    pub fn reseed(&mut self) {
+        self.seed = new_seed();
+        seed_random(self.seed);
+    }
+
+    // how much harder patterns are than they're written
+    pub fn scale(&self) -> Scale {
+        self.difficulty.scale().ranked(self.rank)
+    }
+
+    // what bulletml scripts see as $rank
+    pub fn script_rank(&self) -> f32 {
+        (self.difficulty.rank() + self.rank * 0.25).min(1.0)
+    }
+
+    pub fn change_rank(&mut self, change: f32) {
+        self.rank = (self.rank + change).max(0.0).min(1.0);
+    }
+
+    // puts brackets around the label that's picked out of `group`, and only that one
+    pub fn mark_choice(&mut self, group: &[&str], chosen: &str) {
+        for label in self.labels.values_mut() {
+            let name = label.trim_matches(|c| c == '[' || c == ']').to_string();
+            if group.contains(&name.as_str()) {
+                *label = if name == chosen { format!("[{}]", name) } else { name };
+            }
+        }
+    }
+
+    pub fn choose_shot(&mut self, shot_type: ShotType) {
+        self.shot_type = shot_type;
+        self.shot = shot_type.shot();
+        self.high_scores = HighScores::load(&*self.storage, self.difficulty, shot_type);
+        self.hi_score = self.high_scores.best().max(self.score);
+    }
+
+    // puts the score on the high score table if it's good enough, then starts over
+    pub fn game_over(&mut self) {
+        use stdweb::unstable::TryInto;
+
+        if self.high_scores.qualifies(self.score) {
+            let name = ask("New high score! What's your name?", "");
+            let stage = self.level.lock().map(|level| level.name).unwrap_or("").to_string();
+            let date: f64 = js! ( return Date.now(); ).try_into().unwrap_or(0.0);
+
+            self.high_scores.insert(scores::Entry {
+                name: name.filter(|name| !name.is_empty()).unwrap_or_else(|| "nameless".to_string()),
+                score: self.score,
+                stage,
+                date: date as u64,
+            });
+            self.high_scores.save(&mut *self.storage);
+        }
+
+        js! {
+            alert("You died!");
+            location.reload();
+        }
+    }
+
+    // takes a life off the player and clears the screen so they can carry on,
+    // or ends the game, or the practice run, once they're out of lives.
+    // returns a boolean indicating whether or not there's a next frame to play.
+    pub fn lose_life(&mut self) -> bool {
+        self.change_rank(-DEATH_RANK);
+        self.fail_attacks();
+
+        if self.lives == 0 {
+            if self.practice.is_some() {
+                self.end_practice(false);
+                return true;
+            }
+            self.game_over();
+            return false;
+        }
+
+        self.lives -= 1;
+        self.power = self.power.saturating_sub(POWER_PER_LEVEL);
+        self.invulnerable = RESPAWN_TICKS;
+        if let Some(pos) = self.isos.get_mut(&self.player) {
+            *pos = Isometry2::translation(17.5, 30.0);
+        }
+        self.cancel_bullets(&Vector2::new(17.5, 30.0), std::f32::INFINITY);
+        true
+    }
+
+    // moves on to the next stage once this one's been survived,
+    // or starts a practice run of it over, since that's all it covers.
+    pub fn finish_stage(&mut self, message: &str, next: Level) {
+        if self.practice.is_some() {
+            self.end_practice(true);
+            return;
+        }
+
+        self.clear_stage();
+        js! { alert(@{message}) };
+        self.change_level(next);
+    }
+
+    // whether the practice run is of a single boss phase, which ends along with it
+    pub fn practicing_phase(&self) -> bool {
+        self.practice.as_ref().map_or(false, |practice| practice.phase.is_some())
+    }
+
+    pub fn start_practice(&mut self, practice: Practice) {
+        self.hi_score = practice.load(&*self.storage, self.difficulty, self.shot_type).best;
+        self.practice = Some(practice);
+        self.retry_practice();
+    }
+
+    // puts the practice run in its own record, away from the high score table, and goes again
+    pub fn end_practice(&mut self, cleared: bool) {
+        let practice = match self.practice.clone() {
+            Some(practice) => practice,
+            None => return,
+        };
+        practice.record(&mut *self.storage, self.difficulty, self.shot_type, self.score, cleared);
+        self.retry_practice();
+    }
+
+    // clears everything off the screen and starts the practice run from the top
+    pub fn retry_practice(&mut self) {
+        let practice = match self.practice.clone() {
+            Some(practice) => practice,
+            None => return,
+        };
+
+        let ents = self.isos.keys().filter(|i| **i != self.player).cloned().collect::<Vec<_>>();
+        for ent in ents.iter() {
+            self.delete_ent(ent);
+        }
+
+        self.hi_score = self.hi_score.max(self.score);
+        self.score = 0;
+        self.graze = 0;
+        self.point_items = 0;
+        self.kills = 0;
+        self.rank = 0.0;
+        self.reseed();
+        self.bombs = 3;
+        self.bomb_cooldown = 0;
+        self.invulnerable = 0;
+        self.lives = practice.lives;
+        self.power = practice.power.min(MAX_POWER);
+        if let Some(pos) = self.isos.get_mut(&self.player) {
+            *pos = Isometry2::translation(17.5, 30.0);
+        }
+
+        let stages = Level::stages();
+        let stage = stages[practice.stage.min(stages.len() - 1)];
+        self.change_level(stage());
+
+        // skip straight to the boss phase being practiced
+        if let Some(phase) = practice.phase {
+            let bosses = self.bosses.keys().cloned().collect::<Vec<_>>();
+            for boss in bosses {
+                self.skip_to_phase(boss, phase);
+            }
+        }
+    }
+
+    // every POWER_PER_LEVEL power makes the player's shot a level stronger
+    pub fn power_level(&self) -> usize {
+        self.power / POWER_PER_LEVEL
+    }
+
+    // attaches `child` to `parent`, keeping it `offset` away from it from now on
+    pub fn insert_parent(&mut self, child: usize, parent: Parent) {
+        if let Some(parent_pos) = self.isos.get(&parent.ent).cloned() {
+            self.isos.insert(child, parent.apply(&parent_pos));
+        }
+        self.parents.insert(child, parent);
+    }
+
+    // moves every child to where its parent says it should be.
+    // parents are always placed before their children, so chains of them work too.
+    pub fn propagate_transforms(&mut self) {
+        let parents = &self.parents;
+        let depth = |mut ent: usize| {
+            let mut depth = 0;
+            // bailing out after that many steps in case something got attached to itself
+            while let Some(parent) = parents.get(&ent) {
+                if depth > parents.len() {
+                    break;
+                }
+                ent = parent.ent;
+                depth += 1;
+            }
+            depth
+        };
+
+        let mut children = parents.keys().map(|i_child| (depth(*i_child), *i_child)).collect::<Vec<_>>();
+        children.sort();
+
+        for (_, i_child) in children {
+            let parent = self.parents.get_mut(&i_child).expect("child with no parent");
+            parent.update();
+            if let Some(parent_pos) = self.isos.get(&parent.ent).cloned() {
+                self.isos.insert(i_child, parent.apply(&parent_pos));
+            }
+        }
+    }
+
+    // gives the player as many satellites as their power level calls for
+    pub fn arrange_satellites(&mut self) {
+        let wanted = self.power_level().min(MAX_SATELLITES);
+        let mut satellites = self.satellites.keys().cloned().collect::<Vec<_>>();
+        satellites.sort();
+
+        while satellites.len() > wanted {
+            if let Some(satellite) = satellites.pop() {
+                self.delete_ent(&satellite);
+            }
+        }
+        for _ in satellites.len()..wanted {
+            let ent = self.entity();
+
+            // they start on the player and spread out from there
+            self.insert_parent(ent, Parent::new(self.player, Isometry2::identity()));
+            self.satellites.insert(ent, Satellite::new(self.shot_type.satellite_shot()));
+            self.appearances.insert(ent, "Flower4".to_string());
+        }
+    }
+
     pub fn player(&mut self) -> usize {
         self.player = self.entity();
         self.player
@@ -454,14 +1287,15 @@ impl Game {
 fn main() {
     stdweb::initialize();
 
-    let mut game = Game::new(Level::first());
+    let mut game = Game::new(Level::title());
 
     // if you instantiate an entity before this,
     // I will byte you. (also the program won't work)
     let player = game.player();
     game.isos
         .insert(player, Isometry2::translation(35.0 / 2.0, 35.0 / 2.0));
-    game.hitboxes.insert(player, Cuboid::new(Vector2::new(1.0, 1.0)));
+    game.hitboxes
+        .insert(player, ShapeHandle::new(Cuboid::new(Vector2::new(1.0, 1.0))));
     game.appearances
         .insert(player, "Heart".to_string());
 
@@ -472,43 +1306,59 @@ fn main() {
             .controls
             .update(&mut game.isos.get_mut(&game.player).unwrap());
         let player_pos = game.isos[&game.player];
+        game.change_rank(RANK_PER_TICK);
 
-        if should_shoot {
-            if game.shooting_cooldown == 0 {
-                game.insert_bullet(
-                    "Flower3",
-                    player_pos.clone(),
-                    Cuboid::new(Vector2::new(1.0, 1.0)),
-                    Bullet {
-                        kind: BulletKind::Straight(Vector2::y() * -0.4),
-                        kills: Kills::Bad,
-                    },
-                );
-                game.shooting_cooldown = 5;
-            } else {
-                game.shooting_cooldown -= 1;
-            }
+        // satellites move into formation around the player
+        game.arrange_satellites();
+        let focusing = game.controls.focusing();
+        let mut satellites = game.satellites.keys().cloned().collect::<Vec<_>>();
+        satellites.sort();
+        for (i, i_satellite) in satellites.iter().enumerate() {
+            let satellite = game.satellites.get_mut(i_satellite).expect("satellite went missing");
+            let parent = game.parents.get_mut(i_satellite).expect("satellite with no parent");
+            satellite.update(i, satellites.len(), focusing, &mut parent.offset);
+        }
+
+        // bombs clear every bullet on the screen into point items
+        if game.bomb_cooldown > 0 {
+            game.bomb_cooldown -= 1;
+        } else if game.controls.bombing() && game.bombs > 0 {
+            game.bombs -= 1;
+            game.bomb_cooldown = 60;
+            game.fail_attacks();
+            game.change_rank(-BOMB_RANK);
+            game.cancel_bullets(&player_pos.translation.vector, std::f32::INFINITY);
         }
 
         let mut dead = Vec::new();
+        // enemies that were shot down, rather than just leaving the screen
+        let mut killed = Vec::new();
+        // enemies that took damage this tick, so the renderer can flash them
+        let mut hit = Vec::new();
+        let mut player_died = false;
 
-        // delete anything with a hitbox when it goes off the screen
+        // bullets that bounce are kept on the play field until they run out of bounces,
+        // anything else with a hitbox is deleted when it goes off the screen.
+        let field = game.field();
         for i_hitbox in game.hitboxes.keys() {
+            let pos = game.isos.get_mut(i_hitbox).expect("bullet with no pos");
+
+            if let Some(bullet) = game.bullets.get_mut(i_hitbox) {
+                if bullet.bounce(pos, &field) {
+                    continue;
+                }
+                if bullet.confined && !on_field(&pos.translation.vector, &field) {
+                    dead.push(*i_hitbox);
+                    continue;
+                }
+            }
+
             if !game.screen.contains_point(
                 &Isometry2::identity(),
-                &na::Point::from(
-                    game.isos
-                        .get(i_hitbox)
-                        .expect("bullet with no pos")
-                        .translation
-                        .vector,
-                ),
+                &na::Point::from(pos.translation.vector),
             ) {
                 if *i_hitbox == game.player {
-                    js! {
-                        alert("You died!");
-                        location.reload();
-                    }
+                    player_died = true;
                 } else {
                     dead.push(*i_hitbox);
                 }
@@ -520,24 +1370,149 @@ fn main() {
             let mut enemy_pos = game.isos.get_mut(i_enemy).expect("enemy with no pos");
 
             enemy.update(&mut enemy_pos);
+
+            if let Some(behaviour) = game.behaviours.get_mut(i_enemy) {
+                behaviour.update(enemy, &enemy_pos.translation.vector, &player_pos.translation.vector);
+            }
+
+            // enemies on their way out are gone once they can't be seen
+            if enemy.exit.is_some() && !on_field(&enemy_pos.translation.vector, &field) {
+                dead.push(*i_enemy);
+            }
+        }
+
+        // now that everything has moved, children catch up with their parents
+        game.propagate_transforms();
+
+        if should_shoot {
+            if game.shooting_cooldown == 0 {
+                for bullet in game.shot.fire(game.power_level()) {
+                    let (appearance, hitbox) = (game.shot.appearance.clone(), game.shot.hitbox.clone());
+                    game.insert_bullet_handle(appearance, player_pos.clone(), hitbox, bullet);
+                }
+                game.shooting_cooldown = game.shot.interval;
+            } else {
+                game.shooting_cooldown -= 1;
+            }
+
+            let mut fired = Vec::new();
+            for (i_satellite, satellite) in game.satellites.iter_mut() {
+                if satellite.cooldown > 0 {
+                    continue;
+                }
+                satellite.cooldown = satellite.shot.interval;
+
+                let pos = game.isos[i_satellite];
+                for bullet in satellite.shot.fire(0) {
+                    fired.push((satellite.shot.appearance.clone(), satellite.shot.hitbox.clone(), pos, bullet));
+                }
+            }
+            for (appearance, hitbox, pos, bullet) in fired {
+                game.insert_bullet_handle(appearance, pos, hitbox, bullet);
+            }
+        }
+
+        // fire whatever the emitters have ready this tick
+        let mut fired = Vec::new();
+        let scale = game.scale();
+        for (i_ent, emitters) in game.emitters.iter_mut() {
+            if game.behaviours.get(i_ent).map_or(false, |behaviour| !behaviour.shooting()) {
+                continue;
+            }
+            let pos = game.isos.get(i_ent).expect("emitter with no pos").translation.vector;
+
+            for emitter in emitters.iter_mut() {
+                for bullet in emitter.update(&pos, &player_pos.translation.vector, &scale) {
+                    fired.push((emitter.appearance.clone(), emitter.hitbox.clone(), pos, bullet));
+                }
+            }
+        }
+        for (appearance, hitbox, pos, bullet) in fired {
+            game.insert_bullet_handle(appearance, Isometry2::new(pos, 0.0), hitbox, bullet);
+        }
+
+        // run bulletml scripts, which can fire bullets and steer them as well
+        let mut shots = Vec::new();
+        let rank = game.script_rank();
+        for (i_ent, script) in game.scripts.iter_mut() {
+            let pos = game.isos.get(i_ent).expect("script with no pos").translation.vector;
+            script.rank = rank;
+
+            for shot in script.update(&pos, &player_pos.translation.vector) {
+                shots.push((pos, shot));
+            }
+            if let Some(bullet) = game.bullets.get_mut(i_ent) {
+                bullet.kind = BulletKind::Straight(script.velocity());
+            }
+            if script.vanished() {
+                dead.push(*i_ent);
+            }
+        }
+        for (pos, shot) in shots {
+            let ent = game.insert_bullet_handle(
+                shot.appearance,
+                Isometry2::new(pos, 0.0),
+                shot.hitbox,
+                Bullet::straight(shot.velocity, Kills::Good),
+            );
+            if let Some(script) = shot.script {
+                game.insert_script(ent, script);
+            }
+        }
+
+        // homing bullets turn toward whichever enemy is closest
+        let isos = &game.isos;
+        for (i_bullet, bullet) in game.bullets.iter_mut() {
+            if bullet.homing.is_none() {
+                continue;
+            }
+            let pos = isos.get(i_bullet).expect("bullet with no pos").translation.vector;
+            let closest = game
+                .enemies
+                .keys()
+                .map(|i_enemy| isos.get(i_enemy).expect("enemy with no pos").translation.vector)
+                .min_by(|a, b| {
+                    (a - pos)
+                        .magnitude()
+                        .partial_cmp(&(b - pos).magnitude())
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+            if let Some(target) = closest {
+                bullet.steer(&pos, &target);
+            }
         }
 
         // (index of bullet, bullet)
         for (i_bullet, bullet) in game.bullets.iter_mut() {
             bullet.update(&mut game.isos.get_mut(i_bullet).expect("bullet with no pos"));
 
+            if bullet.expired() {
+                dead.push(*i_bullet);
+                continue;
+            }
+            if !bullet.harmful() {
+                continue;
+            }
+
             let bullet_hitbox = game.hitboxes.get(i_bullet).expect("bullet with no hitbox");
             let bullet_pos = game.isos.get(i_bullet).expect("bullet with no pos");
 
             match bullet.kills {
                 Kills::Good => {
-                    if bullet_hitbox
-                        .contains_point(bullet_pos, &na::Point::from(player_pos.translation.vector))
-                    {
-                        js! {
-                            alert("you died!");
-                            location.reload();
-                        }
+                    let player_point = na::Point::from(player_pos.translation.vector);
+                    let (hit, distance) = match bullet_hitbox.as_point_query() {
+                        Some(hitbox) => (
+                            hitbox.contains_point(bullet_pos, &player_point),
+                            hitbox.distance_to_point(bullet_pos, &player_point, true),
+                        ),
+                        None => (false, std::f32::INFINITY),
+                    };
+                    if hit {
+                        player_died = true;
+                    } else if !bullet.grazed && distance <= GRAZE_RADIUS {
+                        bullet.grazed = true;
+                        game.graze += 1;
+                        game.add_score(GRAZE_SCORE);
                     }
                 }
                 Kills::Bad => {
@@ -548,11 +1523,15 @@ fn main() {
 
                         use nc::query::{proximity, Proximity};
 
-                        match proximity(bullet_pos, bullet_hitbox, enemy_pos, enemy_hitbox, 0.1) {
+                        match proximity(bullet_pos, &**bullet_hitbox, enemy_pos, &**enemy_hitbox, 0.1) {
                             Proximity::Intersecting => {
                                 dead.push(*i_bullet);
-                                if enemy.damage() {
-                                    dead.push(*i_enemy);
+                                let damage = enemy.damage(bullet.damage);
+                                if damage.dealt > 0 {
+                                    hit.push(*i_enemy);
+                                }
+                                if damage.killed {
+                                    killed.push(*i_enemy);
                                 }
                             }
                             _ => {}
@@ -562,11 +1541,151 @@ fn main() {
             }
         }
 
+        // (index of laser, laser)
+        for (i_laser, laser) in game.lasers.iter_mut() {
+            let laser_pos = {
+                let laser_pos = game.isos.get_mut(i_laser).expect("laser with no pos");
+                laser.update(laser_pos);
+                laser_pos.clone()
+            };
+
+            if laser.is_done() {
+                dead.push(*i_laser);
+                continue;
+            }
+
+            match laser.kills {
+                Kills::Good => {
+                    if laser.hits(&laser_pos, &na::Point::from(player_pos.translation.vector)) {
+                        player_died = true;
+                    }
+                }
+                Kills::Bad => {
+                    if !laser.is_active() {
+                        continue;
+                    }
+
+                    for (i_enemy, enemy) in game.enemies.iter_mut() {
+                        let enemy_hitbox =
+                            game.hitboxes.get(i_enemy).expect("enemy with no hitbox");
+                        let enemy_pos = game.isos.get(i_enemy).expect("enemy with no pos");
+
+                        use nc::query::{proximity, Proximity};
+
+                        match proximity(
+                            &laser_pos,
+                            &laser.segment(),
+                            enemy_pos,
+                            &**enemy_hitbox,
+                            laser.width / 2.0,
+                        ) {
+                            Proximity::Disjoint => {}
+                            _ => {
+                                let damage = enemy.damage(1);
+                                if damage.dealt > 0 {
+                                    hit.push(*i_enemy);
+                                }
+                                if damage.killed {
+                                    killed.push(*i_enemy);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // pickups go to whoever gets close enough to them,
+        // or all of them do once the player heads up near the top of the screen.
+        let auto_collect = player_pos.translation.vector.y < AUTO_COLLECT_LINE;
+        let player_hitbox = game.hitboxes.get(&game.player).expect("player with no hitbox");
+        let mut collected = Vec::new();
+        for (i_pickup, pickup) in game.pickups.iter_mut() {
+            use nc::query::{proximity, Proximity};
+
+            let pickup_pos = game.isos.get_mut(i_pickup).expect("pickup with no pos");
+            let pickup_hitbox = game.hitboxes.get(i_pickup).expect("pickup with no hitbox");
+            if auto_collect {
+                pickup.homing = true;
+            }
+            pickup.update(pickup_pos, &player_pos.translation.vector);
+
+            match proximity(pickup_pos, &**pickup_hitbox, &player_pos, &**player_hitbox, COLLECT_RADIUS) {
+                Proximity::Disjoint => {}
+                _ => collected.push(*i_pickup),
+            }
+        }
+        for i_pickup in collected {
+            if let Some(pickup) = game.pickups.remove(&i_pickup) {
+                let pos = game.isos[&i_pickup].translation.vector;
+                game.collect(&pickup, &pos);
+            }
+            game.delete_ent(&i_pickup);
+        }
+
+        // bosses move on when they run out of time, and leave after their last phase
+        let mut timed_out = Vec::new();
+        for (i_boss, boss) in game.bosses.iter_mut() {
+            if boss.update() {
+                timed_out.push(*i_boss);
+            }
+        }
+        for i_boss in timed_out {
+            // running out of time has already moved it on, so being shot down
+            // on the same tick doesn't end this phase or the next one as well
+            killed.retain(|kill| *kill != i_boss);
+            game.end_attack(i_boss, false);
+            // outlasting the phase being practiced counts as clearing it
+            if game.practicing_phase() {
+                game.end_practice(true);
+                killed.clear();
+                player_died = false;
+                break;
+            }
+            if !game.next_phase(i_boss) {
+                dead.push(i_boss);
+            }
+        }
+
+        // enemies take the bullets around them down when they die
+        killed.sort();
+        killed.dedup();
+        for kill in killed.iter() {
+            game.end_attack(*kill, true);
+            // the run is over once the phase being practiced is
+            if game.bosses.contains_key(kill) && game.practicing_phase() {
+                game.end_practice(true);
+                player_died = false;
+                break;
+            }
+            // unless they're a boss with more to give
+            if game.next_phase(*kill) {
+                continue;
+            }
+
+            if let Some(pos) = game.isos.get(kill).map(|iso| iso.translation.vector) {
+                game.cancel_bullets(&pos, CANCEL_RADIUS);
+            }
+            game.drop_items(*kill);
+            if let Some(value) = game.enemies.get(kill).map(|enemy| enemy.value) {
+                game.add_score(value);
+            }
+            game.kills += 1;
+            game.delete_ent(kill);
+        }
+
         // actually kill the bullets
         for kill in dead.iter() {
             game.delete_ent(kill);
         }
 
+        if game.invulnerable > 0 {
+            game.invulnerable -= 1;
+        } else if player_died && !game.lose_life() {
+            // the page reloads after this, so there's no next frame to ask for
+            return;
+        }
+
         let level = game.level.clone();
         if let Ok(level) = level.lock() {
             (level.update)(&mut game);
@@ -574,16 +1693,66 @@ fn main() {
 
         let render_data = renderer::RenderData {
             ents: (0..game.max_entity())
+                .filter(|i| !game.lasers.contains_key(i))
                 .filter_map(|i| {
                     Some(renderer::MeshBundle {
                         ent: i,
+                        // sprites are drawn around the unrotated shape, then rotated with the iso
                         size: game.hitboxes
                             .get(&i)
-                            .map(|hb| hb.half_extents())
-                            .unwrap_or(&Vector2::repeat(1.0))
-                            .clone(),
+                            .map(|hb| hb.local_aabb().half_extents())
+                            .unwrap_or(Vector2::repeat(1.0)),
                         appearance: game.appearances.get(&i)?.clone(),
                         iso: game.isos.get(&i)?.clone(),
+                        alpha: match game.bullets.get(&i) {
+                            Some(bullet) => bullet.alpha(),
+                            // the player is see-through while they can't be hit
+                            None if i == game.player && game.invulnerable > 0 => 0.5,
+                            None => 1.0,
+                        },
+                    })
+                })
+                .collect::<Vec<_>>(),
+            lasers: game
+                .lasers
+                .iter()
+                .filter_map(|(i, laser)| {
+                    Some(renderer::LaserBundle {
+                        ent: *i,
+                        appearance: game.appearances.get(i)?.clone(),
+                        iso: game.isos.get(i)?.clone(),
+                        length: laser.length,
+                        width: laser.width,
+                        warning: !laser.is_active(),
+                    })
+                })
+                .collect::<Vec<_>>(),
+            boss: game.bosses.iter().next().and_then(|(i, boss)| {
+                Some(renderer::BossBar {
+                    health: game.enemies.get(i)?.health as f32 / boss.current().health as f32,
+                    phases_left: boss.phases_left(),
+                    attack: boss.current().name.clone(),
+                })
+            }),
+            hits: hit,
+            hud: renderer::Hud {
+                score: game.score,
+                hi_score: game.hi_score,
+                multiplier: game.multiplier(),
+                lives: game.lives,
+                bombs: game.bombs,
+                power: game.power,
+                graze: game.graze,
+                stage: game.level.lock().map(|level| level.name).unwrap_or("").to_string(),
+                boss_timer: game.bosses.values().next().map(|boss| boss.timer),
+            },
+            labels: game
+                .labels
+                .iter()
+                .filter_map(|(i, text)| {
+                    Some(renderer::Label {
+                        text: text.clone(),
+                        pos: game.isos.get(i)?.translation.vector,
                     })
                 })
                 .collect::<Vec<_>>(),
@@ -0,0 +1,35 @@
+use super::prelude::*;
+
+// attaches an entity to another, so it moves along with it
+#[derive(Clone)]
+pub struct Parent {
+    pub ent: usize,
+    // where the child sits, relative to the parent
+    pub offset: Isometry2<f32>,
+    // radians the offset turns around the parent every tick, for things that orbit
+    pub spin: f32,
+}
+
+impl Parent {
+    pub fn new(ent: usize, offset: Isometry2<f32>) -> Self {
+        Parent {
+            ent,
+            offset,
+            spin: 0.0,
+        }
+    }
+
+    pub fn spinning(mut self, spin: f32) -> Self {
+        self.spin = spin;
+        self
+    }
+
+    pub fn update(&mut self) {
+        self.offset = na::UnitComplex::new(self.spin) * self.offset;
+    }
+
+    // where the child is, given where the parent is
+    pub fn apply(&self, parent: &Isometry2<f32>) -> Isometry2<f32> {
+        parent * self.offset
+    }
+}
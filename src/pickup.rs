@@ -0,0 +1,57 @@
+use super::prelude::*;
+
+// how fast pickups fly at the player once they start homing
+const HOMING_SPEED: f32 = 0.6;
+// how fast pickups can drift down the screen
+const MAX_FALL: f32 = 0.15;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PickupKind {
+    Point,
+    // makes the player's shots stronger
+    Power,
+    Life,
+    Bomb,
+}
+
+#[derive(Clone)]
+pub struct Pickup {
+    pub kind: PickupKind,
+    pub velocity: Vector2<f32>,
+    // homing pickups fly straight at the player instead of drifting down
+    pub homing: bool,
+}
+
+impl Pickup {
+    // pops up a little, then drifts down the screen
+    pub fn new(kind: PickupKind) -> Self {
+        Pickup {
+            kind,
+            velocity: Vector2::y() * -0.3,
+            homing: false,
+        }
+    }
+
+    pub fn homing(kind: PickupKind) -> Self {
+        Pickup {
+            kind,
+            velocity: na::zero(),
+            homing: true,
+        }
+    }
+
+    pub fn update(&mut self, pos: &mut Isometry2<f32>, player: &Vector2<f32>) {
+        if self.homing {
+            let to_player = player - pos.translation.vector;
+            self.velocity = if to_player.magnitude() > HOMING_SPEED {
+                to_player.normalize() * HOMING_SPEED
+            } else {
+                to_player
+            };
+        } else {
+            self.velocity.y = (self.velocity.y + 0.01).min(MAX_FALL);
+        }
+
+        pos.translation.vector += self.velocity;
+    }
+}
@@ -0,0 +1,88 @@
+use super::difficulty::Difficulty;
+use super::shot::ShotType;
+use super::storage::Storage;
+
+// a run of a single stage, or a single boss phase, that starts over whenever it ends
+#[derive(Clone)]
+pub struct Practice {
+    // index into Level::stages
+    pub stage: usize,
+    // the boss phase to skip to, if only that's being practiced
+    pub phase: Option<usize>,
+    pub lives: usize,
+    pub power: usize,
+}
+
+// how practice runs of one stage or phase have gone, kept apart from the high score tables
+#[derive(Clone, Default)]
+pub struct PracticeRecord {
+    pub attempts: usize,
+    pub clears: usize,
+    pub best: usize,
+}
+
+impl Practice {
+    // asks the player what they'd like to practice, and how
+    pub fn ask() -> Self {
+        let number = |question: &str, default: &str| {
+            super::ask(question, default).and_then(|answer| answer.trim().parse::<usize>().ok())
+        };
+
+        let stage = number("Practice which stage? (1 to 5)", "1").unwrap_or(1).max(1) - 1;
+        let phase = number("Start from which boss phase? (leave empty for the whole stage)", "")
+            .map(|phase| phase.max(1) - 1);
+        let lives = number("How many lives?", "2").unwrap_or(2);
+        let power = number("How much power? (0 to 100)", "0").unwrap_or(0);
+
+        Practice {
+            stage,
+            phase,
+            lives,
+            power,
+        }
+    }
+
+    fn key(&self, difficulty: Difficulty, shot_type: ShotType) -> String {
+        let phase = self.phase.map(|phase| phase.to_string()).unwrap_or_else(|| "all".to_string());
+        format!(
+            "degen practice {} {} {} {}",
+            difficulty.name(),
+            shot_type.name(),
+            self.stage,
+            phase
+        )
+    }
+
+    // saved as "attempts clears best"
+    pub fn load(&self, storage: &dyn Storage, difficulty: Difficulty, shot_type: ShotType) -> PracticeRecord {
+        let saved = storage.load(&self.key(difficulty, shot_type)).unwrap_or_default();
+        let mut fields = saved.split_whitespace().map(|field| field.parse().unwrap_or(0));
+
+        PracticeRecord {
+            attempts: fields.next().unwrap_or(0),
+            clears: fields.next().unwrap_or(0),
+            best: fields.next().unwrap_or(0),
+        }
+    }
+
+    pub fn record(
+        &self,
+        storage: &mut dyn Storage,
+        difficulty: Difficulty,
+        shot_type: ShotType,
+        score: usize,
+        cleared: bool,
+    ) {
+        let mut record = self.load(storage, difficulty, shot_type);
+        record.attempts += 1;
+        if cleared {
+            record.clears += 1;
+        }
+        record.best = record.best.max(score);
+
+        let saved = format!("{} {} {}", record.attempts, record.clears, record.best);
+        if let Err(e) = storage.save(&self.key(difficulty, shot_type), &saved) {
+            console!(error, e);
+        }
+    }
+}
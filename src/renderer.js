@@ -6,9 +6,11 @@ document.body.innerHTML += '<canvas id="canv"></canvas>';
 
 const xSize = 350;
 const ySize = 350;
+// the hud goes in a panel to the right of the play field
+const panelSize = 150;
 
 var c = document.getElementById("canv");
-c.style.width = xSize;
+c.style.width = xSize + panelSize;
 c.style.height = ySize;
 c.width = window.innerWidth;
 c.height = window.innerHeight;
@@ -17,14 +19,21 @@ var ctx = c.getContext("2d");
 
 ctx.font = "15px Arial";
 
-ctx.scale(window.innerWidth/xSize, window.innerHeight/ySize);
+ctx.scale(window.innerWidth/(xSize + panelSize), window.innerHeight/ySize);
 
 let imgs = {
 	"Heart": "Heart.png",
 	"Eye": "Eye.jpg",
 	"Flower3": "Flower3.png",
+	"Flower4": "Flower4.png",
 	"Background": "StageBackground.png",
-	"Little Doll": "doll.png"
+	"Little Doll": "doll.png",
+	"Laser": "Petals.png",
+	"Syringe": "Syringe.jpg",
+	"Point": "Petals2.png",
+	"Power": "Flower1.png",
+	"Life": "Heart.png",
+	"Bomb": "Flower2.png"
 };
 
 for (let img in imgs) {
@@ -34,7 +43,12 @@ for (let img in imgs) {
 	imgs[img] = new_img;
 }
 
-function render({ents}) {
+// frames left to flash each entity that got hit, by ent
+let flashes = {};
+
+function render({ents, lasers, boss, hits, labels, hud}) {
+	hits.forEach((ent) => flashes[ent] = 4);
+
 	ctx.fillStyle = "white";
 	ctx.globalAlpha = 0.45;
 	ctx.drawImage(
@@ -57,17 +71,101 @@ function render({ents}) {
 
 	ents.forEach((r) => {
 		//ctx.fillText(r.appearance, r.iso.translation[0] * 10.0, r.iso.translation[1] * 10.0);
+		ctx.save();
+		ctx.globalAlpha = r.alpha;
+		if (flashes[r.ent] > 0) {
+			ctx.filter = "brightness(300%)";
+			flashes[r.ent]--;
+		}
+		// spin the sprite around its own center
+		ctx.translate(r.iso.translation[0] * 10.0, r.iso.translation[1] * 10.0);
+		ctx.rotate(Math.atan2(r.iso.rotation[1], r.iso.rotation[0]));
 		ctx.drawImage(
 			imgs[r.appearance],
-			(r.iso.translation[0] - r.size[0]) * 10.0,
-			(r.iso.translation[1] - r.size[1]) * 10.0,
+			-r.size[0] * 10.0,
+			-r.size[1] * 10.0,
 			r.size[0] * 20.0,
 			r.size[1] * 20.0,
 		);
+		ctx.restore();
 
 		ctx.fillStyle = "black";
 		ctx.globalAlpha = 1;
 	});
 
+	lasers.forEach((l) => {
+		ctx.save();
+		// lasers fire along their local y axis, so stretch the sprite down it
+		ctx.translate(l.iso.translation[0] * 10.0, l.iso.translation[1] * 10.0);
+		ctx.rotate(Math.atan2(l.iso.rotation[1], l.iso.rotation[0]));
+
+		// a faint, thin line until the beam actually becomes dangerous
+		const width = l.warning ? l.width * 0.2 : l.width;
+		ctx.globalAlpha = l.warning ? 0.3 : 1;
+		ctx.drawImage(
+			imgs[l.appearance],
+			-width * 5.0,
+			0,
+			width * 10.0,
+			l.length * 10.0,
+		);
+
+		ctx.restore();
+	});
+
 	ctx.restore();
-}; 
+
+	labels.forEach((l) => {
+		ctx.textAlign = "center";
+		ctx.fillText(l.text, l.pos[0] * 10.0, l.pos[1] * 10.0);
+		ctx.textAlign = "left";
+	});
+
+	if (boss) {
+		// health bar along the top, with a pip for each phase left
+		ctx.fillStyle = "black";
+		ctx.globalAlpha = 0.3;
+		ctx.fillRect(10, 5, xSize - 20, 5);
+		ctx.fillStyle = "crimson";
+		ctx.globalAlpha = 1;
+		ctx.fillRect(10, 5, (xSize - 20) * boss.health, 5);
+		for (let i = 0; i < boss.phases_left; i++)
+			ctx.fillRect(10 + i * 8, 13, 5, 5);
+
+		ctx.fillStyle = "black";
+
+		// spell card title, just under the bar
+		if (boss.attack) {
+			ctx.textAlign = "right";
+			ctx.fillText(boss.attack, xSize - 10, 30);
+			ctx.textAlign = "left";
+		}
+	}
+
+	drawHud(hud);
+};
+
+function drawHud(hud) {
+	ctx.fillStyle = "lavender";
+	ctx.globalAlpha = 1;
+	ctx.fillRect(xSize, 0, panelSize, ySize);
+
+	ctx.fillStyle = "black";
+	ctx.textAlign = "left";
+	const lines = [
+		hud.stage,
+		"",
+		"Hi-Score  " + hud.hi_score,
+		"Score  " + hud.score,
+		"  x" + hud.multiplier.toFixed(2),
+		"",
+		"Lives  " + hud.lives,
+		"Bombs  " + hud.bombs,
+		"Power  " + hud.power,
+		"Graze  " + hud.graze,
+	];
+	if (hud.boss_timer != null)
+		lines.push("", "Time  " + Math.ceil(hud.boss_timer / 60));
+
+	lines.forEach((line, i) => ctx.fillText(line, xSize + 10, 25 + i * 20));
+}
@@ -6,10 +6,59 @@ pub struct MeshBundle {
     pub appearance: String,
     pub size: Vector2<f32>,
     pub iso: Isometry2<f32>,
+    // bullets fade in while they're harmless, and fade out as they despawn
+    pub alpha: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LaserBundle {
+    pub ent: usize,
+    pub appearance: String,
+    pub iso: Isometry2<f32>,
+    pub length: f32,
+    pub width: f32,
+    // still showing where it's going to be, can't hurt anyone yet
+    pub warning: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BossBar {
+    // how much of the current phase's health is left, from 0 to 1
+    pub health: f32,
+    pub phases_left: usize,
+    // the name of the spell card being used, if it's using one
+    pub attack: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Label {
+    pub text: String,
+    pub pos: Vector2<f32>,
+}
+
+// everything shown in the panel beside the play field
+#[derive(Serialize, Deserialize)]
+pub struct Hud {
+    pub score: usize,
+    pub hi_score: usize,
+    pub multiplier: f32,
+    pub lives: usize,
+    pub bombs: usize,
+    pub power: usize,
+    pub graze: usize,
+    pub stage: String,
+    // ticks left before the boss's phase runs out of time, if there's a boss
+    pub boss_timer: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct RenderData {
     pub ents: Vec<MeshBundle>,
+    pub lasers: Vec<LaserBundle>,
+    pub boss: Option<BossBar>,
+    // entities that got hurt this tick
+    pub hits: Vec<usize>,
+    pub labels: Vec<Label>,
+    pub hud: Hud,
 }
 js_serializable!(RenderData);
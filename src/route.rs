@@ -0,0 +1,209 @@
+use super::prelude::*;
+
+// how many pieces a curve is split into when measuring how long it is
+const LENGTH_SAMPLES: usize = 16;
+
+#[derive(Clone, Copy)]
+pub enum Curve {
+    Straight,
+    // bends smoothly through the nodes on either side of the segment
+    CatmullRom,
+    // bends toward two control points on the way to the node
+    Bezier(Vector2<f32>, Vector2<f32>),
+}
+
+#[derive(Clone, Copy)]
+pub enum Easing {
+    Linear,
+    // starts slow
+    In,
+    // ends slow
+    Out,
+    // starts and ends slow
+    InOut,
+}
+
+impl Easing {
+    // takes how far along a segment something is, from 0 to 1,
+    // and returns how far along it should be drawn.
+    pub fn apply(self, t: f32) -> f32 {
+        use Easing::*;
+
+        match self {
+            Linear => t,
+            In => t * t,
+            Out => t * (2.0 - t),
+            InOut if t < 0.5 => 2.0 * t * t,
+            InOut => -1.0 + (4.0 - 2.0 * t) * t,
+        }
+    }
+}
+
+// what happens once the last node of a route is reached
+#[derive(Clone, Copy, PartialEq)]
+pub enum Mode {
+    // head back to the first node and go around again
+    Loop,
+    // go back through the nodes in reverse, then forwards again
+    PingPong,
+    // stay at the last node
+    Hold,
+    // keep going the way it was heading until it's off the screen
+    Exit,
+}
+
+#[derive(Clone)]
+pub struct Node {
+    pub point: Vector2<f32>,
+    // how the path gets from the last node to this one
+    pub curve: Curve,
+    pub easing: Easing,
+    // units per tick on the way to this node, if not the route's speed
+    pub speed: Option<f32>,
+    // ticks to stop for once this node is reached
+    pub wait: usize,
+}
+
+#[derive(Clone)]
+pub struct Route {
+    pub nodes: Vec<Node>,
+    pub speed: f32,
+    pub mode: Mode,
+}
+
+impl Route {
+    pub fn new(speed: f32) -> Self {
+        Route {
+            nodes: Vec::new(),
+            speed,
+            mode: Mode::Loop,
+        }
+    }
+
+    // a route that goes from point to point in straight lines
+    pub fn straight(points: Vec<Vector2<f32>>, speed: f32) -> Self {
+        points
+            .into_iter()
+            .fold(Route::new(speed), |route, point| route.to(point))
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    // the same path, but starting from the last node and heading to the first
+    pub fn reversed(&self) -> Self {
+        let last = self.nodes.len().saturating_sub(1);
+        let nodes = (0..self.nodes.len())
+            .rev()
+            .map(|i| {
+                // the way into a node going backwards is the way out of it going forwards
+                let out = &self.nodes[(i + 1).min(last)];
+                Node {
+                    point: self.nodes[i].point,
+                    curve: match out.curve {
+                        Curve::Bezier(a, b) => Curve::Bezier(b, a),
+                        curve => curve,
+                    },
+                    easing: out.easing,
+                    speed: out.speed,
+                    wait: self.nodes[i].wait,
+                }
+            })
+            .collect();
+
+        Route {
+            nodes,
+            speed: self.speed,
+            mode: self.mode,
+        }
+    }
+
+    fn node(mut self, point: Vector2<f32>, curve: Curve) -> Self {
+        self.nodes.push(Node {
+            point,
+            curve,
+            easing: Easing::Linear,
+            speed: None,
+            wait: 0,
+        });
+        self
+    }
+
+    pub fn to(self, point: Vector2<f32>) -> Self {
+        self.node(point, Curve::Straight)
+    }
+
+    pub fn curve_to(self, point: Vector2<f32>) -> Self {
+        self.node(point, Curve::CatmullRom)
+    }
+
+    pub fn bezier_to(self, a: Vector2<f32>, b: Vector2<f32>, point: Vector2<f32>) -> Self {
+        self.node(point, Curve::Bezier(a, b))
+    }
+
+    // the rest of these change the last node added
+
+    pub fn wait(mut self, ticks: usize) -> Self {
+        if let Some(node) = self.nodes.last_mut() {
+            node.wait = ticks;
+        }
+        self
+    }
+
+    pub fn speed(mut self, speed: f32) -> Self {
+        if let Some(node) = self.nodes.last_mut() {
+            node.speed = Some(speed);
+        }
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        if let Some(node) = self.nodes.last_mut() {
+            node.easing = easing;
+        }
+        self
+    }
+
+    // where something `t` of the way from `from` to the node at `goal` is.
+    // `prev` is where it was before `from`, which catmull-rom curves bend around.
+    pub fn point_at(&self, goal: usize, prev: &Vector2<f32>, from: &Vector2<f32>, t: f32) -> Vector2<f32> {
+        let node = &self.nodes[goal];
+        let to = node.point;
+
+        match node.curve {
+            Curve::Straight => from + (to - from) * t,
+            Curve::CatmullRom => {
+                // only a looping route carries on to the first node after the last
+                let next = match self.nodes.get(goal + 1) {
+                    Some(next) => next.point,
+                    None if self.mode == Mode::Loop => self.nodes[0].point,
+                    None => to,
+                };
+                let (t2, t3) = (t * t, t * t * t);
+
+                (from * 2.0
+                    + (to - prev) * t
+                    + (prev * 2.0 - from * 5.0 + to * 4.0 - next) * t2
+                    + (from * 3.0 - prev - to * 3.0 + next) * t3)
+                    * 0.5
+            }
+            Curve::Bezier(a, b) => {
+                let u = 1.0 - t;
+                from * (u * u * u) + a * (3.0 * u * u * t) + b * (3.0 * u * t * t) + to * (t * t * t)
+            }
+        }
+    }
+
+    // roughly how far it is along the path from `from` to the node at `goal`
+    pub fn length(&self, goal: usize, prev: &Vector2<f32>, from: &Vector2<f32>) -> f32 {
+        (1..=LENGTH_SAMPLES)
+            .map(|i| {
+                let t0 = (i - 1) as f32 / LENGTH_SAMPLES as f32;
+                let t1 = i as f32 / LENGTH_SAMPLES as f32;
+                (self.point_at(goal, prev, from, t1) - self.point_at(goal, prev, from, t0)).magnitude()
+            })
+            .sum()
+    }
+}
@@ -0,0 +1,48 @@
+use super::prelude::*;
+use super::shot::Shot;
+use std::f32::consts::PI;
+
+// how far from the player satellites orbit
+const ORBIT_RADIUS: f32 = 3.0;
+// radians satellites go around the player every tick
+const ORBIT_SPEED: f32 = 0.05;
+// how much of the way to their spot in the formation satellites move every tick
+const REARRANGE_SPEED: f32 = 0.2;
+
+// a little turret that follows the player around and shoots alongside them
+#[derive(Clone)]
+pub struct Satellite {
+    pub shot: Shot,
+    // ticks until it can fire again
+    pub cooldown: usize,
+    // how far around the player the formation has turned
+    pub orbit: f32,
+}
+
+impl Satellite {
+    pub fn new(shot: Shot) -> Self {
+        Satellite {
+            shot,
+            cooldown: 0,
+            orbit: 0.0,
+        }
+    }
+
+    // moves the satellite toward its spot as number `i` of `count`,
+    // circling the player normally and lining up in front of them while focusing.
+    pub fn update(&mut self, i: usize, count: usize, focusing: bool, offset: &mut Isometry2<f32>) {
+        self.orbit = (self.orbit + ORBIT_SPEED) % (2.0 * PI);
+        self.cooldown = self.cooldown.saturating_sub(1);
+
+        let goal = if focusing {
+            let x = i as f32 - (count - 1) as f32 / 2.0;
+            Vector2::new(x * 1.5, -2.0)
+        } else {
+            let angle = self.orbit + 2.0 * PI * i as f32 / count as f32;
+            Vector2::new(angle.cos(), angle.sin()) * ORBIT_RADIUS
+        };
+
+        let vec = &mut offset.translation.vector;
+        *vec += (goal - *vec) * REARRANGE_SPEED;
+    }
+}
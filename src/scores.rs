@@ -0,0 +1,195 @@
+use super::difficulty::Difficulty;
+use super::shot::ShotType;
+use super::storage::Storage;
+
+// how many scores each table keeps
+const TABLE_SIZE: usize = 10;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub score: usize,
+    // how far the player got
+    pub stage: String,
+    // when the score was set, in milliseconds since the unix epoch
+    pub date: u64,
+}
+
+// the best scores for one difficulty and shot type
+pub struct HighScores {
+    pub difficulty: Difficulty,
+    pub shot_type: ShotType,
+    // best first
+    pub entries: Vec<Entry>,
+}
+
+impl HighScores {
+    fn key(difficulty: Difficulty, shot_type: ShotType) -> String {
+        format!("degen scores {} {}", difficulty.name(), shot_type.name())
+    }
+
+    // saved as one "score date stage|name" line per entry
+    pub fn load(storage: &dyn Storage, difficulty: Difficulty, shot_type: ShotType) -> Self {
+        let saved = storage.load(&Self::key(difficulty, shot_type)).unwrap_or_default();
+
+        let entries = saved
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, ' ');
+                let score = fields.next()?.parse().ok()?;
+                let date = fields.next()?.parse().ok()?;
+                let mut rest = fields.next()?.splitn(2, '|');
+                let stage = rest.next()?.to_string();
+                let name = rest.next()?.to_string();
+                Some(Entry {
+                    name,
+                    score,
+                    stage,
+                    date,
+                })
+            })
+            .take(TABLE_SIZE)
+            .collect();
+
+        HighScores {
+            difficulty,
+            shot_type,
+            entries,
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) {
+        let saved = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{} {} {}|{}",
+                    entry.score,
+                    entry.date,
+                    entry.stage,
+                    entry.name
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if let Err(e) = storage.save(&Self::key(self.difficulty, self.shot_type), &saved) {
+            console!(error, e);
+        }
+    }
+
+    pub fn best(&self) -> usize {
+        self.entries.first().map(|entry| entry.score).unwrap_or(0)
+    }
+
+    // whether or not `score` is good enough to go on the table
+    pub fn qualifies(&self, score: usize) -> bool {
+        score > 0
+            && (self.entries.len() < TABLE_SIZE
+                || self.entries.last().map_or(true, |entry| score > entry.score))
+    }
+
+    pub fn insert(&mut self, entry: Entry) {
+        let at = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(at, entry);
+        self.entries.truncate(TABLE_SIZE);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::storage::FileStorage;
+
+    fn entry(name: &str, score: usize) -> Entry {
+        Entry {
+            name: name.to_string(),
+            score,
+            stage: "Stage 3".to_string(),
+            date: 1_600_000_000_000,
+        }
+    }
+
+    fn table() -> HighScores {
+        HighScores {
+            difficulty: Difficulty::Hard,
+            shot_type: ShotType::Focused,
+            entries: Vec::new(),
+        }
+    }
+
+    // a folder of its own under the system's temp folder, emptied out first
+    fn temp_storage(name: &str) -> FileStorage {
+        let dir = std::env::temp_dir().join(format!("degen-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        FileStorage::new(dir)
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut storage = temp_storage("scores");
+        let mut scores = table();
+        scores.insert(entry("reimu", 5000));
+        scores.insert(Entry {
+            date: 1_700_000_000_000,
+            ..entry("marisa | kirisame", 9000)
+        });
+        scores.save(&mut storage);
+
+        let loaded = HighScores::load(&storage, Difficulty::Hard, ShotType::Focused);
+        assert_eq!(loaded.entries, scores.entries);
+        assert_eq!(loaded.best(), 9000);
+
+        // other tables are kept apart
+        let other = HighScores::load(&storage, Difficulty::Easy, ShotType::Focused);
+        assert!(other.entries.is_empty());
+
+        let _ = std::fs::remove_dir_all(&storage.dir);
+    }
+
+    #[test]
+    fn inserts_best_first() {
+        let mut scores = table();
+        for (i, score) in [300, 100, 500, 200, 400, 300].iter().enumerate() {
+            scores.insert(entry(&i.to_string(), *score));
+        }
+
+        let order = scores.entries.iter().map(|entry| entry.score).collect::<Vec<_>>();
+        assert_eq!(order, vec![500, 400, 300, 300, 200, 100]);
+        // ties go below whoever got there first
+        assert_eq!(scores.entries[2].name, "0");
+        assert_eq!(scores.entries[3].name, "5");
+    }
+
+    #[test]
+    fn keeps_the_best_few() {
+        let mut scores = table();
+        for score in 1..=TABLE_SIZE * 2 {
+            scores.insert(entry("", score * 10));
+        }
+
+        assert_eq!(scores.entries.len(), TABLE_SIZE);
+        assert_eq!(scores.best(), TABLE_SIZE * 20);
+        assert_eq!(scores.entries.last().unwrap().score, (TABLE_SIZE + 1) * 10);
+    }
+
+    #[test]
+    fn qualifies() {
+        let mut scores = table();
+        assert!(!scores.qualifies(0));
+        assert!(scores.qualifies(1));
+
+        for score in 1..=TABLE_SIZE {
+            scores.insert(entry("", score * 10));
+        }
+        // a full table only takes scores that beat the worst one
+        assert!(!scores.qualifies(5));
+        assert!(!scores.qualifies(10));
+        assert!(scores.qualifies(11));
+    }
+}
@@ -0,0 +1,123 @@
+use super::bullet::{Bullet, BulletKills, BulletKind};
+use super::emitter::spread;
+use super::prelude::*;
+use std::f32::consts::PI;
+
+// how much power it takes to go up a power level
+pub const POWER_PER_LEVEL: usize = 25;
+
+// the kinds of weapon the player can pick from on the title screen
+#[derive(Clone, Copy, PartialEq)]
+pub enum ShotType {
+    // lots of weak bullets fanning out over the screen
+    Spread,
+    // a few strong bullets straight ahead
+    Focused,
+    // bullets that turn toward the closest enemy
+    Homing,
+}
+
+impl ShotType {
+    pub const ALL: [ShotType; 3] = [ShotType::Spread, ShotType::Focused, ShotType::Homing];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ShotType::Spread => "Spread",
+            ShotType::Focused => "Focused",
+            ShotType::Homing => "Homing",
+        }
+    }
+
+    pub fn shot(self) -> Shot {
+        match self {
+            ShotType::Spread => Shot::new("Flower3", Cuboid::new(Vector2::new(1.0, 1.0)), 0.4, 5)
+                .streams(1, 2)
+                .spread(0.0, 0.25),
+            ShotType::Focused => Shot::new("Flower3", Cuboid::new(Vector2::new(0.6, 1.0)), 0.7, 4)
+                .streams(1, 1)
+                .spread(0.0, 0.03)
+                .bullet(Bullet::straight(na::zero(), BulletKills::Bad).damage(2)),
+            ShotType::Homing => Shot::new("Flower3", Cuboid::new(Vector2::new(0.8, 0.8)), 0.35, 7)
+                .streams(1, 1)
+                .spread(0.4, 0.2)
+                .bullet(Bullet::straight(na::zero(), BulletKills::Bad).homing(0.08)),
+        }
+    }
+}
+
+impl ShotType {
+    // what the player's satellites fire, which doesn't grow with power
+    pub fn satellite_shot(self) -> Shot {
+        let shot = Shot::new("Flower4", Cuboid::new(Vector2::new(0.6, 0.6)), 0.5, 8);
+        match self {
+            ShotType::Spread | ShotType::Focused => shot,
+            ShotType::Homing => shot.bullet(Bullet::straight(na::zero(), BulletKills::Bad).homing(0.08)),
+        }
+    }
+}
+
+// what the player fires, and how it grows with their power
+#[derive(Clone)]
+pub struct Shot {
+    pub appearance: String,
+    pub hitbox: ShapeHandle<f32>,
+    // what each of the player's bullets starts out as, before it's pointed up its stream
+    pub bullet: Bullet,
+    pub speed: f32,
+    // ticks the player has to wait after shooting before they can shoot again
+    pub interval: usize,
+    // bullets in a volley at power level 0, and how many more each level adds
+    pub streams: usize,
+    pub streams_per_level: usize,
+    // radians the volley fans out over at power level 0, and how much wider each level makes it
+    pub spread: f32,
+    pub spread_per_level: f32,
+}
+
+impl Shot {
+    pub fn new<S: Into<String>, H: Shape<f32>>(appearance: S, hitbox: H, speed: f32, interval: usize) -> Self {
+        Shot {
+            appearance: appearance.into(),
+            hitbox: ShapeHandle::new(hitbox),
+            bullet: Bullet::straight(na::zero(), BulletKills::Bad),
+            speed,
+            interval,
+            streams: 1,
+            streams_per_level: 0,
+            spread: 0.0,
+            spread_per_level: 0.0,
+        }
+    }
+
+    pub fn streams(mut self, streams: usize, per_level: usize) -> Self {
+        self.streams = streams;
+        self.streams_per_level = per_level;
+        self
+    }
+
+    pub fn spread(mut self, spread: f32, per_level: f32) -> Self {
+        self.spread = spread;
+        self.spread_per_level = per_level;
+        self
+    }
+
+    pub fn bullet(mut self, bullet: Bullet) -> Self {
+        self.bullet = bullet;
+        self
+    }
+
+    // the bullets in one volley at power level `level`, all heading up the screen
+    pub fn fire(&self, level: usize) -> Vec<Bullet> {
+        let count = self.streams + self.streams_per_level * level;
+        let angle = self.spread + self.spread_per_level * level as f32;
+        // straight up the screen, which is toward negative y
+        spread(-PI / 2.0, angle, count, true)
+            .into_iter()
+            .map(|angle| {
+                let mut bullet = self.bullet.clone();
+                bullet.kind = BulletKind::Straight(Vector2::new(angle.cos(), angle.sin()) * self.speed);
+                bullet
+            })
+            .collect()
+    }
+}
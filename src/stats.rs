@@ -0,0 +1,58 @@
+use super::storage::Storage;
+use std::collections::HashMap;
+
+const STATS_KEY: &str = "degen stats";
+
+#[derive(Clone, Default)]
+pub struct AttackRecord {
+    pub attempts: usize,
+    pub captures: usize,
+}
+
+// stats that stick around between sessions, so players can see how they're doing
+#[derive(Default)]
+pub struct Stats {
+    // (name of the boss attack, how it's gone so far)
+    pub attacks: HashMap<String, AttackRecord>,
+}
+
+impl Stats {
+    // saved as one "attempts captures name" line per attack
+    pub fn load(storage: &dyn Storage) -> Self {
+        let saved = storage.load(STATS_KEY).unwrap_or_default();
+
+        let attacks = saved
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, ' ');
+                let attempts = fields.next()?.parse().ok()?;
+                let captures = fields.next()?.parse().ok()?;
+                let name = fields.next()?.to_string();
+                Some((name, AttackRecord { attempts, captures }))
+            })
+            .collect();
+
+        Stats { attacks }
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) {
+        let saved = self
+            .attacks
+            .iter()
+            .map(|(name, record)| format!("{} {} {}", record.attempts, record.captures, name))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        if let Err(e) = storage.save(STATS_KEY, &saved) {
+            console!(error, e);
+        }
+    }
+
+    pub fn attempt(&mut self, attack: &str) {
+        self.attacks.entry(attack.to_string()).or_default().attempts += 1;
+    }
+
+    pub fn capture(&mut self, attack: &str) {
+        self.attacks.entry(attack.to_string()).or_default().captures += 1;
+    }
+}
@@ -0,0 +1,64 @@
+// somewhere to keep things between sessions, like stats and high scores
+pub trait Storage {
+    fn load(&self, key: &str) -> Option<String>;
+    fn save(&mut self, key: &str, value: &str) -> Result<(), String>;
+}
+
+// the browser's local storage, for when the game is running on a web page
+#[cfg(target_arch = "wasm32")]
+pub struct LocalStorage;
+
+#[cfg(target_arch = "wasm32")]
+impl Storage for LocalStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        stdweb::web::window().local_storage().get(key)
+    }
+
+    fn save(&mut self, key: &str, value: &str) -> Result<(), String> {
+        stdweb::web::window()
+            .local_storage()
+            .insert(key, value)
+            .map_err(|_| format!("local storage is full, couldn't save {}", key))
+    }
+}
+
+// a file per key in a folder, for native builds
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    pub dir: std::path::PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn new<P: Into<std::path::PathBuf>>(dir: P) -> Self {
+        FileStorage { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> std::path::PathBuf {
+        self.dir.join(key.replace(|c: char| !c.is_alphanumeric(), "_"))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Storage for FileStorage {
+    fn load(&self, key: &str) -> Option<String> {
+        std::fs::read_to_string(self.path(key)).ok()
+    }
+
+    fn save(&mut self, key: &str, value: &str) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir)
+            .and_then(|_| std::fs::write(self.path(key), value))
+            .map_err(|e| format!("couldn't save {}: {}", key, e))
+    }
+}
+
+// wherever makes sense for what the game was built for
+#[cfg(target_arch = "wasm32")]
+pub fn default_storage() -> Box<dyn Storage> {
+    Box::new(LocalStorage)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn default_storage() -> Box<dyn Storage> {
+    Box::new(FileStorage::new("saves"))
+}